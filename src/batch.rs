@@ -0,0 +1,156 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+use crate::{compare::*, input::*, parser::*};
+
+/// File extensions that are picked up as assembly listings when walking a directory
+const LISTING_EXTENSIONS: [&str; 3] = ["s", "S", "asm"];
+
+/// A listing pair of the batch & the result of comparing it
+#[derive(Debug, Clone)]
+pub struct BatchEntry {
+    /// Relative path of the listing, or the source file name when paired through the `.file` directive
+    pub name: String,
+    pub file1: Option<PathBuf>,
+    pub file2: Option<PathBuf>,
+    pub comparison: Option<Comparison>,
+    pub error: Option<String>,
+}
+
+/// Per file results of a directory comparison & the totals of the whole project
+#[derive(Debug, Clone, Default)]
+pub struct BatchResult {
+    pub entries: Vec<BatchEntry>,
+    pub levenshtein_distance: i32,
+    pub file_length: f64,
+    pub change_percentage: f64,
+    pub overall_distance: i32,
+    pub overall_length: f64,
+    pub overall_change_percentage: f64,
+    pub matched_keys: usize,
+    pub unmatched_keys: usize,
+    pub over_threshold: usize,
+}
+
+/// Recursively collects the listings of a directory, keyed by their path relative to the directory
+pub fn find_listings(dir: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut listings = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| LISTING_EXTENSIONS.contains(&extension))
+            {
+                let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+                listings.insert(relative, path);
+            }
+        }
+    }
+    Ok(listings)
+}
+
+/// Source file names from the `.file` directive that identify exactly one of the given listings
+fn unique_file_names(listings: &BTreeMap<String, PathBuf>) -> HashMap<String, String> {
+    let mut names: HashMap<String, Vec<String>> = HashMap::new();
+    for (relative, path) in listings {
+        if let Some(file_name) = read_binary_file(&path.to_string_lossy()).ok().and_then(|data| extract_file_name(&data)) {
+            names.entry(file_name).or_default().push(relative.clone());
+        }
+    }
+    names
+        .into_iter()
+        .filter(|(_, relatives)| relatives.len() == 1)
+        .map(|(file_name, mut relatives)| (file_name, relatives.remove(0)))
+        .collect()
+}
+
+/// Pairs the listings of two directories
+///
+/// Listings are paired by relative path first. The remaining ones are paired by the source file name of their
+/// `.file` directive when that name is unique in both directories. Listings without a counterpart are returned
+/// with only one side set.
+pub fn pair_listings(dir1: &Path, dir2: &Path) -> Result<Vec<BatchEntry>> {
+    let mut listings1 = find_listings(dir1)?;
+    let mut listings2 = find_listings(dir2)?;
+    let mut entries = Vec::new();
+
+    let common: Vec<String> = listings1.keys().filter(|relative| listings2.contains_key(*relative)).cloned().collect();
+    for relative in common {
+        entries.push(BatchEntry {
+            file1: listings1.remove(&relative),
+            file2: listings2.remove(&relative),
+            name: relative,
+            comparison: None,
+            error: None,
+        });
+    }
+
+    let names1 = unique_file_names(&listings1);
+    let names2 = unique_file_names(&listings2);
+    for (file_name, relative1) in names1 {
+        if let Some(relative2) = names2.get(&file_name) {
+            entries.push(BatchEntry {
+                name: file_name,
+                file1: listings1.remove(&relative1),
+                file2: listings2.remove(relative2),
+                comparison: None,
+                error: None,
+            });
+        }
+    }
+
+    for (relative, path) in listings1 {
+        entries.push(BatchEntry { name: relative, file1: Some(path), file2: None, comparison: None, error: None });
+    }
+    for (relative, path) in listings2 {
+        entries.push(BatchEntry { name: relative, file1: None, file2: Some(path), comparison: None, error: None });
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Compares every listing pair of two directories & rolls the results up into project totals
+///
+/// Listings that exist in only one directory are reported with an error & left out of the totals.
+pub fn compare_directories(dir1: &Path, dir2: &Path, options: &CompareOptions) -> Result<BatchResult> {
+    let mut batch = BatchResult {
+        entries: pair_listings(dir1, dir2)?,
+        ..Default::default()
+    };
+
+    for entry in batch.entries.iter_mut() {
+        let (Some(file1), Some(file2)) = (&entry.file1, &entry.file2) else {
+            entry.error = Some(if entry.file1.is_some() { "Only in directory 1" } else { "Only in directory 2" }.to_string());
+            continue;
+        };
+        match (load_input(file1, &options.parser), load_input(file2, &options.parser)) {
+            (Ok(listing1), Ok(listing2)) => {
+                let comparison = compare_listings(&listing1, &listing2, options);
+                batch.levenshtein_distance += comparison.levenshtein_distance;
+                batch.file_length += comparison.file_length;
+                batch.overall_distance += comparison.overall_distance;
+                batch.overall_length += comparison.overall_length;
+                batch.matched_keys += comparison.matched_keys;
+                batch.unmatched_keys += comparison.unmatched_keys;
+                batch.over_threshold += comparison.over_threshold;
+                entry.comparison = Some(comparison);
+            }
+            (Err(error), _) | (_, Err(error)) => entry.error = Some(format!("Failed to read: {}", error)),
+        }
+    }
+
+    batch.change_percentage = percentage(batch.levenshtein_distance as f64, batch.file_length);
+    batch.overall_change_percentage = percentage(batch.overall_distance as f64, batch.overall_length);
+    Ok(batch)
+}
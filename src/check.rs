@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{batch::*, compare::*, similarity::SimilarityMetric};
+
+/// Exit code of the `check` command when a threshold is exceeded, errors exit with 1
+pub const EXIT_THRESHOLD_EXCEEDED: i32 = 2;
+
+/// Limits of the regression gate, a `None` limit is not checked
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    /// Maximum overall change percentage, unmatched blocks included
+    pub max_change: Option<f64>,
+    /// Maximum change percentage of any single key
+    pub max_key_change: Option<f64>,
+    /// Maximum number of keys that exist in only one listing
+    pub max_unmatched: Option<usize>,
+    /// Minimum normalized similarity of any single key, per metric
+    pub min_key_similarity: SimilarityLimits,
+}
+
+impl Thresholds {
+    pub fn is_empty(&self) -> bool {
+        self.max_change.is_none()
+            && self.max_key_change.is_none()
+            && self.max_unmatched.is_none()
+            && SimilarityMetric::ALL.iter().all(|metric| self.min_key_similarity.get(*metric).is_none())
+    }
+}
+
+/// A limit for each normalized similarity
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimilarityLimits {
+    pub levenshtein: Option<f64>,
+    pub cosine: Option<f64>,
+    pub divergence: Option<f64>,
+    pub classes: Option<f64>,
+    pub weighted: Option<f64>,
+    pub diff: Option<f64>,
+    pub damerau: Option<f64>,
+}
+
+impl SimilarityLimits {
+    pub fn get(&self, metric: SimilarityMetric) -> Option<f64> {
+        *match metric {
+            SimilarityMetric::Levenshtein => &self.levenshtein,
+            SimilarityMetric::Cosine => &self.cosine,
+            SimilarityMetric::Divergence => &self.divergence,
+            SimilarityMetric::Classes => &self.classes,
+            SimilarityMetric::Weighted => &self.weighted,
+            SimilarityMetric::Diff => &self.diff,
+            SimilarityMetric::Damerau => &self.damerau,
+        }
+    }
+
+    pub fn get_mut(&mut self, metric: SimilarityMetric) -> &mut Option<f64> {
+        match metric {
+            SimilarityMetric::Levenshtein => &mut self.levenshtein,
+            SimilarityMetric::Cosine => &mut self.cosine,
+            SimilarityMetric::Divergence => &mut self.divergence,
+            SimilarityMetric::Classes => &mut self.classes,
+            SimilarityMetric::Weighted => &mut self.weighted,
+            SimilarityMetric::Diff => &mut self.diff,
+            SimilarityMetric::Damerau => &mut self.damerau,
+        }
+    }
+}
+
+/// A threshold exceeded by the comparison, `key` is set for the per-key limits
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub key: Option<String>,
+    pub message: String,
+}
+
+/// Checks a comparison against the thresholds & returns every limit it exceeds
+///
+/// Keys whose distance was skipped because of `max_distance` only have a lower bound of their change, so they
+/// always fail the per-key change limit & the Levenshtein similarity limit. Pick a `max_distance` that is large
+/// enough for the blocks being gated.
+pub fn check_comparison(comparison: &Comparison, thresholds: &Thresholds) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if let Some(max_change) = thresholds.max_change {
+        if comparison.overall_change_percentage > max_change {
+            violations.push(Violation {
+                key: None,
+                message: format!(
+                    "Overall change {:.2}% exceeds {:.2}%",
+                    comparison.overall_change_percentage, max_change
+                ),
+            });
+        }
+    }
+
+    if let Some(max_unmatched) = thresholds.max_unmatched {
+        if comparison.unmatched_keys > max_unmatched {
+            violations.push(Violation {
+                key: None,
+                message: format!("{} unmatched key(s) exceed {}", comparison.unmatched_keys, max_unmatched),
+            });
+            for result in comparison.results.iter().filter(|result| !result.is_matched()) {
+                violations.push(Violation { key: Some(result.key.clone()), message: result.change_label() });
+            }
+        }
+    }
+
+    if let Some(max_key_change) = thresholds.max_key_change {
+        for result in &comparison.results {
+            let exceeded = match result.status {
+                KeyStatus::Compared => result.change_percent > max_key_change,
+                KeyStatus::OverThreshold => true,
+                KeyStatus::OnlyInFile1 | KeyStatus::OnlyInFile2 => false,
+            };
+            if exceeded {
+                violations.push(Violation {
+                    key: Some(result.key.clone()),
+                    message: format!("Change {} exceeds {:.2}%", result.change_label(), max_key_change),
+                });
+            }
+        }
+    }
+
+    for metric in SimilarityMetric::ALL {
+        let Some(min_similarity) = thresholds.min_key_similarity.get(metric) else {
+            continue;
+        };
+        for result in &comparison.results {
+            let similarity = result.similarities.get(metric);
+            let below = match result.status {
+                // A negative similarity was not computed
+                KeyStatus::Compared => (0.0..min_similarity).contains(&similarity),
+                // The Levenshtein similarity is an upper bound & the instruction-level metrics are skipped
+                KeyStatus::OverThreshold => metric == SimilarityMetric::Levenshtein || similarity < min_similarity,
+                KeyStatus::OnlyInFile1 | KeyStatus::OnlyInFile2 => false,
+            };
+            if below {
+                violations.push(Violation {
+                    key: Some(result.key.clone()),
+                    message: format!(
+                        "{} {} is below {:.3}",
+                        metric.label(),
+                        result.similarity_of_label(metric),
+                        min_similarity
+                    ),
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+/// Checks one file of a batch comparison
+///
+/// Files that exist in only one directory fail when `max_unmatched` is set & files that could not be read always
+/// fail.
+pub fn check_entry(entry: &BatchEntry, thresholds: &Thresholds) -> Vec<Violation> {
+    match (&entry.comparison, &entry.error) {
+        (Some(comparison), _) => check_comparison(comparison, thresholds),
+        (None, error) => {
+            let one_sided = entry.file1.is_none() || entry.file2.is_none();
+            if one_sided && thresholds.max_unmatched.is_none() {
+                return Vec::new();
+            }
+            vec![Violation { key: None, message: error.clone().unwrap_or_else(|| "Not compared".to_string()) }]
+        }
+    }
+}
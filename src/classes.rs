@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{histogram::Histogram, levenstein::optimized_levenshtein, parser::Dialect};
+
+/// Semantic group of an instruction, shared by every architecture
+///
+/// Floating point instructions count as SIMD, they run on the vector registers on all supported architectures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstructionClass {
+    LoadStore,
+    Arithmetic,
+    Simd,
+    Branch,
+    Call,
+    Other,
+}
+
+impl InstructionClass {
+    pub const ALL: [InstructionClass; 6] = [
+        InstructionClass::LoadStore,
+        InstructionClass::Arithmetic,
+        InstructionClass::Simd,
+        InstructionClass::Branch,
+        InstructionClass::Call,
+        InstructionClass::Other,
+    ];
+
+    /// Name used in the configuration file
+    pub fn name(&self) -> &'static str {
+        match self {
+            InstructionClass::LoadStore => "load_store",
+            InstructionClass::Arithmetic => "arithmetic",
+            InstructionClass::Simd => "simd",
+            InstructionClass::Branch => "branch",
+            InstructionClass::Call => "call",
+            InstructionClass::Other => "other",
+        }
+    }
+
+    /// Name used in the class change summaries
+    pub fn label(&self) -> &'static str {
+        match self {
+            InstructionClass::LoadStore => "load/store",
+            InstructionClass::Arithmetic => "scalar arithmetic",
+            InstructionClass::Simd => "SIMD",
+            InstructionClass::Branch => "branches",
+            InstructionClass::Call => "calls",
+            InstructionClass::Other => "other",
+        }
+    }
+}
+
+use InstructionClass::*;
+
+/// Classification of x86 & x86-64 mnemonics, the first matching pattern wins
+///
+/// A trailing `*` matches a prefix & a leading `*` a suffix, which covers the AT&T size suffixes (`movl`, `movq`)
+/// and the SSE/AVX packed & scalar forms (`addps`, `mulsd`).
+const X86_CLASSES: &[(&str, InstructionClass)] = &[
+    ("call*", Call),
+    ("ret*", Call),
+    ("leave*", Call),
+    ("enter*", Call),
+    ("syscall", Call),
+    ("j*", Branch),
+    ("loop*", Branch),
+    ("popcnt*", Arithmetic),
+    ("push*", LoadStore),
+    ("pop*", LoadStore),
+    ("v*", Simd),
+    ("cvt*", Simd),
+    ("movap*", Simd),
+    ("movup*", Simd),
+    ("movdq*", Simd),
+    ("movhp*", Simd),
+    ("movlp*", Simd),
+    ("movmsk*", Simd),
+    ("unpck*", Simd),
+    ("shufp*", Simd),
+    ("padd*", Simd),
+    ("psub*", Simd),
+    ("pmul*", Simd),
+    ("pand*", Simd),
+    ("por", Simd),
+    ("pxor", Simd),
+    ("psll*", Simd),
+    ("psrl*", Simd),
+    ("psra*", Simd),
+    ("pshuf*", Simd),
+    ("pcmp*", Simd),
+    ("pmov*", Simd),
+    ("pmin*", Simd),
+    ("pmax*", Simd),
+    ("pack*", Simd),
+    ("punpck*", Simd),
+    ("pinsr*", Simd),
+    ("pextr*", Simd),
+    ("palignr", Simd),
+    ("pblend*", Simd),
+    ("ptest", Simd),
+    ("*ps", Simd),
+    ("*pd", Simd),
+    ("*ss", Simd),
+    ("*sd", Simd),
+    ("cmov*", LoadStore),
+    ("mov*", LoadStore),
+    ("xchg*", LoadStore),
+    ("stos*", LoadStore),
+    ("lods*", LoadStore),
+    ("lea*", Arithmetic),
+    ("add*", Arithmetic),
+    ("adc*", Arithmetic),
+    ("sub*", Arithmetic),
+    ("sbb*", Arithmetic),
+    ("imul*", Arithmetic),
+    ("mul*", Arithmetic),
+    ("idiv*", Arithmetic),
+    ("div*", Arithmetic),
+    ("inc*", Arithmetic),
+    ("dec*", Arithmetic),
+    ("neg*", Arithmetic),
+    ("not*", Arithmetic),
+    ("and*", Arithmetic),
+    ("or*", Arithmetic),
+    ("xor*", Arithmetic),
+    ("sh*", Arithmetic),
+    ("sa*", Arithmetic),
+    ("ro*", Arithmetic),
+    ("rc*", Arithmetic),
+    ("cmp*", Arithmetic),
+    ("test*", Arithmetic),
+    ("set*", Arithmetic),
+    ("bt*", Arithmetic),
+    ("bs*", Arithmetic),
+    ("lzcnt*", Arithmetic),
+    ("tzcnt*", Arithmetic),
+    ("cqto", Arithmetic),
+    ("cltq", Arithmetic),
+    ("cltd", Arithmetic),
+    ("cwtl", Arithmetic),
+    ("cdq*", Arithmetic),
+    ("cqo", Arithmetic),
+];
+
+/// Classification of AArch64 mnemonics, the first matching pattern wins
+const AARCH64_CLASSES: &[(&str, InstructionClass)] = &[
+    ("bl", Call),
+    ("blr*", Call),
+    ("ret*", Call),
+    ("b", Branch),
+    ("b.*", Branch),
+    ("br*", Branch),
+    ("cbz", Branch),
+    ("cbnz", Branch),
+    ("tbz", Branch),
+    ("tbnz", Branch),
+    ("ld1*", Simd),
+    ("ld2*", Simd),
+    ("ld3*", Simd),
+    ("ld4*", Simd),
+    ("st1*", Simd),
+    ("st2*", Simd),
+    ("st3*", Simd),
+    ("st4*", Simd),
+    ("f*", Simd),
+    ("scvtf", Simd),
+    ("ucvtf", Simd),
+    ("dup", Simd),
+    ("ins", Simd),
+    ("umov", Simd),
+    ("smov", Simd),
+    ("movi", Simd),
+    ("mvni", Simd),
+    ("ext", Simd),
+    ("tbl", Simd),
+    ("tbx", Simd),
+    ("zip*", Simd),
+    ("uzp*", Simd),
+    ("trn*", Simd),
+    ("rev64", Simd),
+    ("cnt", Simd),
+    ("addv", Simd),
+    ("addp", Simd),
+    ("*maxv", Simd),
+    ("*minv", Simd),
+    ("*xtn*", Simd),
+    ("shl", Simd),
+    ("ushr", Simd),
+    ("sshr", Simd),
+    ("bsl", Simd),
+    ("bit", Simd),
+    ("bif", Simd),
+    ("cmp", Arithmetic),
+    ("cmn", Arithmetic),
+    ("cm*", Simd),
+    ("ld*", LoadStore),
+    ("st*", LoadStore),
+    ("mov*", LoadStore),
+    ("add*", Arithmetic),
+    ("adc*", Arithmetic),
+    ("adr*", Arithmetic),
+    ("sub*", Arithmetic),
+    ("sbc*", Arithmetic),
+    ("neg*", Arithmetic),
+    ("mul", Arithmetic),
+    ("madd", Arithmetic),
+    ("msub", Arithmetic),
+    ("mneg", Arithmetic),
+    ("*mull", Arithmetic),
+    ("*mulh", Arithmetic),
+    ("*div", Arithmetic),
+    ("and*", Arithmetic),
+    ("orr", Arithmetic),
+    ("orn", Arithmetic),
+    ("eor", Arithmetic),
+    ("eon", Arithmetic),
+    ("bic*", Arithmetic),
+    ("mvn", Arithmetic),
+    ("lsl", Arithmetic),
+    ("lsr", Arithmetic),
+    ("asr", Arithmetic),
+    ("ror", Arithmetic),
+    ("tst", Arithmetic),
+    ("ccmp", Arithmetic),
+    ("ccmn", Arithmetic),
+    ("cs*", Arithmetic),
+    ("cinc", Arithmetic),
+    ("cinv", Arithmetic),
+    ("cneg", Arithmetic),
+    ("sxt*", Arithmetic),
+    ("uxt*", Arithmetic),
+    ("ubf*", Arithmetic),
+    ("sbf*", Arithmetic),
+    ("bfi", Arithmetic),
+    ("bfxil", Arithmetic),
+    ("extr", Arithmetic),
+    ("clz", Arithmetic),
+    ("cls", Arithmetic),
+    ("rbit", Arithmetic),
+    ("rev*", Arithmetic),
+];
+
+/// Classification of 32-bit ARM & Thumb mnemonics, the first matching pattern wins
+///
+/// Conditional forms such as `beq` or `addne` share the class of the unconditional instruction.
+const ARM_CLASSES: &[(&str, InstructionClass)] = &[
+    ("bl", Call),
+    ("blx", Call),
+    ("bx", Call),
+    ("v*", Simd),
+    ("bic*", Arithmetic),
+    ("bfi", Arithmetic),
+    ("bfc", Arithmetic),
+    ("b*", Branch),
+    ("cbz", Branch),
+    ("cbnz", Branch),
+    ("push", LoadStore),
+    ("pop", LoadStore),
+    ("ld*", LoadStore),
+    ("st*", LoadStore),
+    ("mov*", LoadStore),
+    ("add*", Arithmetic),
+    ("adc*", Arithmetic),
+    ("adr*", Arithmetic),
+    ("sub*", Arithmetic),
+    ("sbc*", Arithmetic),
+    ("rsb*", Arithmetic),
+    ("rsc*", Arithmetic),
+    ("mul*", Arithmetic),
+    ("mla*", Arithmetic),
+    ("mls*", Arithmetic),
+    ("umull*", Arithmetic),
+    ("smull*", Arithmetic),
+    ("umlal*", Arithmetic),
+    ("smlal*", Arithmetic),
+    ("sdiv*", Arithmetic),
+    ("udiv*", Arithmetic),
+    ("neg*", Arithmetic),
+    ("and*", Arithmetic),
+    ("orr*", Arithmetic),
+    ("eor*", Arithmetic),
+    ("mvn*", Arithmetic),
+    ("lsl*", Arithmetic),
+    ("lsr*", Arithmetic),
+    ("asr*", Arithmetic),
+    ("ror*", Arithmetic),
+    ("cmp*", Arithmetic),
+    ("cmn*", Arithmetic),
+    ("tst*", Arithmetic),
+    ("teq*", Arithmetic),
+    ("clz*", Arithmetic),
+    ("uxt*", Arithmetic),
+    ("sxt*", Arithmetic),
+    ("ubfx*", Arithmetic),
+    ("sbfx*", Arithmetic),
+    ("rev*", Arithmetic),
+];
+
+/// Number of instructions in each class
+pub type ClassHistogram = BTreeMap<InstructionClass, usize>;
+
+/// Whether a mnemonic matches a pattern of the classification tables, `*xtn*` matches anywhere in the mnemonic
+fn matches_pattern(mnemonic: &str, pattern: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix('*') {
+        match prefix.strip_prefix('*') {
+            Some(infix) => mnemonic.contains(infix),
+            None => mnemonic.starts_with(prefix),
+        }
+    } else if let Some(suffix) = pattern.strip_prefix('*') {
+        mnemonic.ends_with(suffix)
+    } else {
+        mnemonic == pattern
+    }
+}
+
+/// Class of a mnemonic in the given dialect, `Other` when none of the patterns match
+pub fn classify(dialect: Dialect, mnemonic: &str) -> InstructionClass {
+    let table = match dialect {
+        Dialect::X86 => X86_CLASSES,
+        Dialect::Aarch64 => AARCH64_CLASSES,
+        Dialect::Arm => ARM_CLASSES,
+    };
+    let mnemonic = mnemonic.to_lowercase();
+    table
+        .iter()
+        .find(|(pattern, _)| matches_pattern(&mnemonic, pattern))
+        .map_or(Other, |(_, class)| *class)
+}
+
+/// Class counts of an opcode histogram
+pub fn class_histogram(histogram: &Histogram, dialect: Dialect) -> ClassHistogram {
+    let mut classes = ClassHistogram::new();
+    for (mnemonic, count) in histogram {
+        *classes.entry(classify(dialect, mnemonic)).or_insert(0) += count;
+    }
+    classes
+}
+
+/// Class histogram keyed by the class labels, for display next to the opcode histograms
+pub fn class_label_histogram(classes: &ClassHistogram) -> Histogram {
+    classes.iter().map(|(class, count)| (class.label().to_string(), *count)).collect()
+}
+
+/// Edit distance between the class sequences of two blocks
+///
+/// Counted in instructions, replacing a mnemonic by another one of the same class (`movl` by `movq`, `addl` by
+/// `leal`) is free, so only changes in what kind of work the block does are counted.
+pub fn class_distance(block1: &str, block2: &str, dialect: Dialect) -> usize {
+    let sequence = |block: &str| -> Vec<u8> {
+        block
+            .lines()
+            .filter(|mnemonic| !mnemonic.is_empty())
+            .map(|mnemonic| classify(dialect, mnemonic) as u8)
+            .collect()
+    };
+    optimized_levenshtein(&sequence(block1), &sequence(block2))
+}
+
+/// Change of the class counts from File 1 to File 2, e.g. `+12 SIMD, −30 scalar arithmetic, −4 branches`
+pub fn class_changes(classes1: &ClassHistogram, classes2: &ClassHistogram) -> String {
+    let changes: Vec<String> = InstructionClass::ALL
+        .iter()
+        .filter_map(|class| {
+            let count1 = classes1.get(class).copied().unwrap_or(0) as i64;
+            let count2 = classes2.get(class).copied().unwrap_or(0) as i64;
+            match count2 - count1 {
+                0 => None,
+                delta if delta > 0 => Some(format!("+{} {}", delta, class.label())),
+                delta => Some(format!("−{} {}", -delta, class.label())),
+            }
+        })
+        .collect();
+    if changes.is_empty() {
+        "no change".to_string()
+    } else {
+        changes.join(", ")
+    }
+}
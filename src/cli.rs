@@ -0,0 +1,656 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{batch::*, check::*, compare::*, compile::*, config::*, git_compare::*, input::*, junit::*, matrix::*, parser::*, report::*, similarity::*, snapshot::*, source_map::*, source_view::*, watch::*};
+
+const USAGE: &str = "Usage: dissimetrics <file1|dir1> <file2|dir2> [file3 ...] [options]
+       dissimetrics compile <source> --flags-a <flags> --flags-b <flags> [--cc-a <compiler>] [--cc-b <compiler>] [options]
+       dissimetrics project <compile_commands.json> --flags-a <flags> --flags-b <flags> [options]
+       dissimetrics check <baseline|dir1> <listing|dir2> [--max-change <%>] [--max-key-change <%>] [--max-unmatched <N>] [--min-key-similarity <metric>=<0-1>] [--junit <results.xml>] [options]
+       dissimetrics snapshot <listing> <output.dsnap> [--compiler <name>] [--flags <flags>]
+       dissimetrics git <repo> <revision_a> <revision_b> --file <path/in/repo.c> --command <compile command> [options]
+Options: [--max-distance <N>] [--threads <N>] [--source1 <old.c> --source2 <new.c>] [--source-root <dir>]
+         [--html <report.html>] [--markdown <report.md>] [--top <N>] [--config <dissimetrics.toml>] [--watch] [--normalized]";
+
+/// Arguments of a command line comparison
+#[derive(Clone)]
+struct CliArgs {
+    /// Two files, two directories, or three & more files for the comparison matrix
+    inputs: Vec<PathBuf>,
+    options: CompareOptions,
+    /// Old & new version of the source file, used to re-key the second listing
+    sources: Option<(PathBuf, PathBuf)>,
+    /// Searched before the directory of the first listing for the source files
+    source_root: Option<PathBuf>,
+    /// Flags & compilers of both sides of the `compile` command
+    flags_a: String,
+    flags_b: String,
+    compiler_a: Option<String>,
+    compiler_b: Option<String>,
+    /// Source file relative to the repository & compiler with flags of the `git` command
+    file: Option<PathBuf>,
+    command: String,
+    /// Limits of the `check` command & where its results are written as JUnit XML
+    thresholds: Thresholds,
+    junit: Option<PathBuf>,
+    /// Metadata saved by the `snapshot` command in place of what is found in the listing
+    snapshot_compiler: Option<String>,
+    snapshot_flags: Option<String>,
+    /// Report files written for a two-file comparison & the number of keys listed in the Markdown one
+    html: Option<PathBuf>,
+    markdown: Option<PathBuf>,
+    top: usize,
+    /// Metrics shown in the tables, from the configuration file
+    metrics: MetricsConfig,
+    /// Compare two listings again whenever one of them changes on disk
+    watch: bool,
+}
+
+/// Listings of a two-file comparison & its results
+struct PairResult {
+    file_name: String,
+    listing1: Listing,
+    listing2: Listing,
+    comparison: Comparison,
+}
+
+fn invalid_input(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("{}\n{}", message, USAGE))
+}
+
+/// Parses the value following an option such as `--threads 4`
+fn parse_value<T: FromStr>(option: &str, value: Option<&String>) -> Result<T> {
+    let value = value.ok_or_else(|| invalid_input(format!("{} needs a value", option)))?;
+    value
+        .parse::<T>()
+        .map_err(|_| invalid_input(format!("Invalid {} value: {}", option, value)))
+}
+
+/// Parses the options after the command, starting from the defaults of the configuration file
+fn parse_args(args: &[String], config: &Config) -> Result<CliArgs> {
+    let mut files = Vec::new();
+    let mut options = config.compare_options();
+    let mut source1 = config.inputs.source1.clone();
+    let mut source2 = config.inputs.source2.clone();
+    let mut source_root = config.inputs.source_root.clone();
+    let mut flags_a = String::new();
+    let mut flags_b = String::new();
+    let mut compiler_a = None;
+    let mut compiler_b = None;
+    let mut file = None;
+    let mut command = DEFAULT_COMPILER.to_string();
+    let mut thresholds = config.thresholds.clone();
+    let mut junit = None;
+    let mut snapshot_compiler = None;
+    let mut snapshot_flags = None;
+    let mut html = None;
+    let mut markdown = None;
+    let mut top = DEFAULT_TOP_KEYS;
+    let mut watch = false;
+    let mut metrics = config.metrics.clone();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--max-distance" => options.max_distance = Some(parse_value(arg, iter.next())?),
+            "--threads" => options.threads = Some(parse_value(arg, iter.next())?),
+            "--source1" => source1 = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--source2" => source2 = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--source-root" => source_root = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--flags-a" => flags_a = parse_value(arg, iter.next())?,
+            "--flags-b" => flags_b = parse_value(arg, iter.next())?,
+            "--cc-a" => compiler_a = Some(parse_value(arg, iter.next())?),
+            "--cc-b" => compiler_b = Some(parse_value(arg, iter.next())?),
+            "--file" => file = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--command" => command = parse_value(arg, iter.next())?,
+            "--max-change" => thresholds.max_change = Some(parse_value(arg, iter.next())?),
+            "--max-key-change" => thresholds.max_key_change = Some(parse_value(arg, iter.next())?),
+            "--max-unmatched" => thresholds.max_unmatched = Some(parse_value(arg, iter.next())?),
+            "--min-key-similarity" => {
+                let limit: String = parse_value(arg, iter.next())?;
+                let (metric, value) = parse_similarity_limit(&limit)?;
+                *thresholds.min_key_similarity.get_mut(metric) = Some(value);
+            }
+            "--junit" => junit = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--compiler" => snapshot_compiler = Some(parse_value(arg, iter.next())?),
+            "--flags" => snapshot_flags = Some(parse_value(arg, iter.next())?),
+            "--html" => html = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--markdown" => markdown = Some(parse_value::<PathBuf>(arg, iter.next())?),
+            "--top" => top = parse_value(arg, iter.next())?,
+            "--watch" => watch = true,
+            "--normalized" => metrics.normalized = true,
+            // Already read by `load_cli_config`
+            "--config" => {
+                parse_value::<PathBuf>(arg, iter.next())?;
+            }
+            _ if arg.starts_with("--") => return Err(invalid_input(format!("Unknown option: {}", arg))),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    let sources = match (source1, source2) {
+        (Some(source1), Some(source2)) => Some((source1, source2)),
+        (None, None) => None,
+        _ => return Err(invalid_input("--source1 and --source2 must be given together".to_string())),
+    };
+    options.skipped_metrics = skipped_metrics(&metrics, &thresholds);
+
+    Ok(CliArgs {
+        inputs: files,
+        options,
+        sources,
+        source_root,
+        flags_a,
+        flags_b,
+        compiler_a,
+        compiler_b,
+        file,
+        command,
+        thresholds,
+        junit,
+        snapshot_compiler,
+        snapshot_flags,
+        html,
+        markdown,
+        top,
+        metrics,
+        watch,
+    })
+}
+
+/// Parses a `<metric>=<similarity>` limit such as `levenshtein=0.6`
+fn parse_similarity_limit(limit: &str) -> Result<(SimilarityMetric, f64)> {
+    let names: Vec<&str> = SimilarityMetric::ALL.iter().map(|metric| metric.name()).collect();
+    let invalid = || invalid_input(format!("Invalid similarity limit {}, expected <{}>=<0-1>", limit, names.join("|")));
+    let (name, value) = limit.split_once('=').ok_or_else(invalid)?;
+    let metric = SimilarityMetric::ALL.into_iter().find(|metric| metric.name() == name).ok_or_else(invalid)?;
+    let value: f64 = value.parse().map_err(|_| invalid())?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err(invalid());
+    }
+    Ok((metric, value))
+}
+
+/// Prints the summary & per-key tables with the columns of the enabled metrics
+fn print_comparison(file_name: &str, comparison: &Comparison, source_lines: &HashMap<String, String>, metrics: &MetricsConfig) {
+    let levenshtein = metrics.is_enabled("levenshtein");
+    let cosine = metrics.is_enabled("cosine");
+    let change = metrics.is_enabled("change");
+    let divergence = metrics.is_enabled("divergence");
+    let classes = metrics.is_enabled("classes");
+    let weighted = metrics.is_enabled("weighted");
+    let diff = metrics.is_enabled("diff");
+    let damerau = metrics.is_enabled("damerau");
+    let column = |enabled: bool, value: String, width: usize| if enabled { format!(" {:>width$}", value, width = width) } else { String::new() };
+    let class_changes = |value: &str| if classes { format!("  {:<50}", value) } else { String::new() };
+    let similarity_metrics = metrics.similarity_metrics();
+    let similarity_columns = |value: &dyn Fn(SimilarityMetric) -> String| -> String {
+        similarity_metrics.iter().map(|metric| format!(" {:>16}", value(*metric))).collect()
+    };
+
+    println!("File Name: {}", file_name);
+    println!(
+        "{:<22}{}{}{}{}{}{}  Derivation",
+        "Scope",
+        column(levenshtein, "Levenshtein Distance".to_string(), 22),
+        column(weighted, "Weighted Distance".to_string(), 18),
+        column(cosine, "Cosine Similarity".to_string(), 18),
+        column(change, "Change %".to_string(), 12),
+        column(divergence, "JS Divergence".to_string(), 14),
+        similarity_columns(&|metric| metric.label().to_string()),
+    );
+    for row in comparison.summary_rows() {
+        println!(
+            "{:<22}{}{}{}{}{}{}  {}",
+            row.scope,
+            column(levenshtein, row.distance, 22),
+            column(weighted, row.weighted_distance, 18),
+            column(cosine, format!("{:.2}", comparison.cosine_similarity), 18),
+            column(change, row.change, 12),
+            column(divergence, format!("{:.3}", comparison.divergence), 14),
+            similarity_columns(&|metric| format!("{:.3}", row.similarities.get(metric))),
+            row.derivation
+        );
+    }
+    if classes {
+        println!(
+            "Instruction classes: {} (class distance {} over matched keys)",
+            comparison.class_changes, comparison.class_distance
+        );
+    }
+    if diff {
+        println!(
+            "Diff: {} changed line(s) in {} hunk(s), {} unchanged (LCS) over matched keys",
+            comparison.changed_lines, comparison.hunks, comparison.lcs_length
+        );
+    }
+    if damerau {
+        println!(
+            "Reordering: {} edit(s) saved by adjacent swaps, Damerau-Levenshtein {} (OSA {}) over matched keys",
+            comparison.swaps, comparison.damerau_distance, comparison.osa_distance
+        );
+    }
+    println!();
+
+    println!(
+        "{:<40}{}{}{}{:>13}{}{}{}{}{}{}{}{}{}{}{}  Source",
+        "Key",
+        column(levenshtein, "Levenshtein Distance".to_string(), 22),
+        column(weighted, "Weighted Distance".to_string(), 18),
+        column(cosine, "Cosine Similarity".to_string(), 18),
+        "Block Length",
+        column(change, "Change %".to_string(), 16),
+        column(divergence, "JS Divergence".to_string(), 14),
+        column(classes, "Class Distance".to_string(), 15),
+        column(diff, "LCS".to_string(), 6),
+        column(diff, "Hunks".to_string(), 6),
+        column(diff, "Changed Lines".to_string(), 14),
+        column(damerau, "OSA".to_string(), 6),
+        column(damerau, "Damerau".to_string(), 8),
+        column(damerau, "Swaps".to_string(), 6),
+        similarity_columns(&|metric| metric.label().to_string()),
+        class_changes("Class Changes"),
+    );
+    for result in &comparison.results {
+        let source = source_lines.get(&result.key).map(|line| line.as_str()).unwrap_or("-");
+        println!(
+            "{:<40}{}{}{}{:>13}{}{}{}{}{}{}{}{}{}{}{}  {}",
+            result.key,
+            column(levenshtein, result.distance_label(), 22),
+            column(weighted, result.weighted_distance_label(), 18),
+            column(cosine, result.similarity_label(), 18),
+            result.block_length,
+            column(change, result.change_label(), 16),
+            column(divergence, result.divergence_label(), 14),
+            column(classes, result.class_distance_label(), 15),
+            column(diff, result.count_label(result.lcs_length), 6),
+            column(diff, result.count_label(result.hunks), 6),
+            column(diff, result.count_label(result.changed_lines), 14),
+            column(damerau, result.count_label(result.osa_distance), 6),
+            column(damerau, result.count_label(result.damerau_distance), 8),
+            column(damerau, result.count_label(result.swaps), 6),
+            similarity_columns(&|metric| result.similarity_of_label(metric)),
+            class_changes(&result.class_changes),
+            source
+        );
+    }
+}
+
+fn print_batch(batch: &BatchResult) {
+    let total_prefix = if batch.over_threshold > 0 { ">= " } else { "" };
+
+    println!(
+        "{:<40} {:>10} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        "File", "Matched", "Unmatched", "Distance", "Change %", "Overall", "Overall %"
+    );
+    for entry in &batch.entries {
+        match (&entry.comparison, &entry.error) {
+            (Some(comparison), _) => println!(
+                "{:<40} {:>10} {:>10} {:>12} {:>11.2}% {:>12} {:>11.2}%",
+                entry.name,
+                comparison.matched_keys,
+                comparison.unmatched_keys,
+                comparison.levenshtein_distance,
+                comparison.change_percentage,
+                comparison.overall_distance,
+                comparison.overall_change_percentage
+            ),
+            (None, error) => println!("{:<40} {}", entry.name, error.as_deref().unwrap_or("N/A")),
+        }
+    }
+    println!(
+        "{:<40} {:>10} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        "Project total",
+        batch.matched_keys,
+        batch.unmatched_keys,
+        format!("{}{}", total_prefix, batch.levenshtein_distance),
+        format!("{}{:.2}%", total_prefix, batch.change_percentage),
+        format!("{}{}", total_prefix, batch.overall_distance),
+        format!("{}{:.2}%", total_prefix, batch.overall_change_percentage)
+    );
+}
+
+fn print_matrix(matrix: &ComparisonMatrix) {
+    let width = matrix.labels.iter().map(|label| label.len()).max().unwrap_or(0).max(12);
+    for metric in [MatrixMetric::Distance, MatrixMetric::CosineSimilarity, MatrixMetric::ChangePercentage] {
+        println!("{}", metric.name());
+        print!("{:<width$}", "", width = width);
+        for label in &matrix.labels {
+            print!(" {:>width$}", label, width = width);
+        }
+        println!();
+        for (label, row) in matrix.labels.iter().zip(&matrix.cells) {
+            print!("{:<width$}", label, width = width);
+            for cell in row {
+                print!(" {:>width$}", metric.label(cell), width = width);
+            }
+            println!();
+        }
+        println!();
+    }
+}
+
+/// Loads & compares two listings, re-keying the second one when both sources are given
+fn compare_pair(file1: &Path, file2: &Path, cli_args: &CliArgs) -> Result<PairResult> {
+    let listing1 = load_input(file1, &cli_args.options.parser)?;
+    let mut listing2 = load_input(file2, &cli_args.options.parser)?;
+    if let Some((source1, source2)) = &cli_args.sources {
+        listing2 = remap_listing(&listing2, source1, source2)?;
+    }
+
+    let file_name = match (&listing1.file_name, &listing2.file_name) {
+        (Some(name1), Some(name2)) if name1 == name2 => name1.clone(),
+        (Some(name1), Some(name2)) => {
+            println!("Files do not match: {} != {}", name1, name2);
+            name1.clone()
+        }
+        _ => return Err(Error::new(ErrorKind::InvalidData, "One or both file names not found.")),
+    };
+
+    let comparison = compare_listings(&listing1, &listing2, &cli_args.options);
+    Ok(PairResult { file_name, listing1, listing2, comparison })
+}
+
+/// Compares two listings & prints the summary & per-key tables, and writes the reports when requested
+fn run_pair(file1: &Path, file2: &Path, cli_args: &CliArgs) -> Result<Comparison> {
+    let pair = compare_pair(file1, file2, cli_args)?;
+    let roots = source_roots(cli_args.source_root.as_deref(), file1);
+    let source_lines = load_source_lines(pair.comparison.results.iter().map(|result| result.key.as_str()), &roots);
+    print_comparison(&pair.file_name, &pair.comparison, &source_lines, &cli_args.metrics);
+
+    let report = Report {
+        file_name: &pair.file_name,
+        comparison: &pair.comparison,
+        listing1: &pair.listing1,
+        listing2: &pair.listing2,
+        source_lines: &source_lines,
+    };
+    if let Some(path) = &cli_args.html {
+        report.write_html(path)?;
+        println!("HTML report written to {}", path.display());
+    }
+    if let Some(path) = &cli_args.markdown {
+        report.write_markdown(path, cli_args.top)?;
+        println!("Markdown report written to {}", path.display());
+    }
+    Ok(pair.comparison)
+}
+
+/// Compares two listings, then again every time one of them or a remapping source changes, until interrupted
+///
+/// Errors after the first comparison are printed & watching goes on, the files may be rewritten again.
+fn run_watch(file1: &Path, file2: &Path, cli_args: &CliArgs) -> Result<()> {
+    let mut paths = vec![file1.to_path_buf(), file2.to_path_buf()];
+    if let Some((source1, source2)) = &cli_args.sources {
+        paths.extend([source1.clone(), source2.clone()]);
+    }
+    let mut watcher = FileWatcher::new(paths);
+    let mut previous = run_pair(file1, file2, cli_args)?;
+
+    loop {
+        println!("\nWatching {} file(s) for changes, press Ctrl+C to stop", watcher.paths().len());
+        while !watcher.poll() {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        println!();
+        match run_pair(file1, file2, cli_args) {
+            Ok(comparison) => {
+                let mut changed: Vec<String> = changed_keys(&previous, &comparison).into_iter().collect();
+                changed.sort_by(|a, b| key_order(a).cmp(&key_order(b)));
+                if changed.is_empty() {
+                    println!("No key changed since the previous run");
+                } else {
+                    println!("Changed since the previous run: {}", changed.join(", "));
+                }
+                previous = comparison;
+            }
+            Err(error) => eprintln!("Error: {}", error),
+        }
+    }
+}
+
+/// Compares two files, two directories in batch mode, or three & more files as a matrix
+fn run_compare(cli_args: &CliArgs) -> Result<()> {
+    if cli_args.inputs.len() < 2 {
+        return Err(invalid_input("Expected at least two files or two directories".to_string()));
+    }
+
+    if cli_args.watch && (cli_args.inputs.len() != 2 || cli_args.inputs.iter().any(|input| input.is_dir())) {
+        return Err(invalid_input("--watch needs exactly two listings".to_string()));
+    }
+
+    if cli_args.inputs.len() > 2 {
+        if cli_args.sources.is_some() {
+            return Err(invalid_input("--source1 and --source2 need exactly two listings".to_string()));
+        }
+        let matrix = compare_matrix(&cli_args.inputs, &cli_args.options)?;
+        print_matrix(&matrix);
+        return Ok(());
+    }
+
+    let (file1, file2) = (&cli_args.inputs[0], &cli_args.inputs[1]);
+    if file1.is_dir() && file2.is_dir() {
+        if cli_args.sources.is_some() {
+            return Err(invalid_input("--source1 and --source2 need exactly two listings".to_string()));
+        }
+        let batch = compare_directories(file1, file2, &cli_args.options)?;
+        print_batch(&batch);
+        return Ok(());
+    }
+    if cli_args.watch {
+        return run_watch(file1, file2, cli_args);
+    }
+    run_pair(file1, file2, cli_args).map(|_| ())
+}
+
+/// Compiles one source file with two flag sets & compares the generated listings
+fn run_compile(cli_args: &CliArgs) -> Result<()> {
+    let [source] = cli_args.inputs.as_slice() else {
+        return Err(invalid_input("compile expects exactly one source file".to_string()));
+    };
+
+    let side_a = CompileSide {
+        compiler: cli_args.compiler_a.clone().unwrap_or_else(|| DEFAULT_COMPILER.to_string()),
+        flags: split_flags(&cli_args.flags_a),
+    };
+    let side_b = CompileSide {
+        compiler: cli_args.compiler_b.clone().unwrap_or_else(|| side_a.compiler.clone()),
+        flags: split_flags(&cli_args.flags_b),
+    };
+    // The temp directory is removed once the listings are compared
+    let (_dir, listing_a, listing_b) = compile_pair(source, &side_a, &side_b)?;
+
+    // The listings live in a temp directory, the sources are next to the compiled file
+    let pair_args = CliArgs {
+        inputs: Vec::new(),
+        source_root: source.parent().map(|dir| dir.to_path_buf()),
+        ..cli_args.clone()
+    };
+    run_pair(&listing_a, &listing_b, &pair_args).map(|_| ())
+}
+
+/// Re-compiles every translation unit of a compilation database with both flag deltas & compares the results
+fn run_project(cli_args: &CliArgs) -> Result<()> {
+    let [compile_commands] = cli_args.inputs.as_slice() else {
+        return Err(invalid_input("project expects exactly one compile_commands.json".to_string()));
+    };
+
+    let project = compile_project(compile_commands, &split_flags(&cli_args.flags_a), &split_flags(&cli_args.flags_b))?;
+    for error in &project.errors {
+        eprintln!("{}", error);
+    }
+    let batch = compare_directories(&project.dir_a, &project.dir_b, &cli_args.options)?;
+    print_batch(&batch);
+    Ok(())
+}
+
+fn print_violations(violations: &[Violation]) {
+    for violation in violations {
+        match &violation.key {
+            Some(key) => println!("  {:<40} {}", key, violation.message),
+            None => println!("  {}", violation.message),
+        }
+    }
+}
+
+/// Checks every listing pair of two directories & returns the exit code of the regression gate
+fn run_check_batch(dir1: &Path, dir2: &Path, cli_args: &CliArgs) -> Result<i32> {
+    let batch = compare_directories(dir1, dir2, &cli_args.options)?;
+    if let Some(path) = &cli_args.junit {
+        fs::write(path, batch_junit(&batch, &cli_args.thresholds, &cli_args.options.parser))?;
+    }
+
+    let mut failed = 0;
+    for entry in &batch.entries {
+        let violations = check_entry(entry, &cli_args.thresholds);
+        if violations.is_empty() {
+            println!("PASS {}", entry.name);
+        } else {
+            println!("FAIL {}", entry.name);
+            print_violations(&violations);
+            failed += 1;
+        }
+    }
+    println!("{} of {} file(s) failed", failed, batch.entries.len());
+    Ok(if failed > 0 { EXIT_THRESHOLD_EXCEEDED } else { 0 })
+}
+
+/// Compares a listing against its baseline, or two directories file by file, & returns the exit code of the
+/// regression gate
+fn run_check(cli_args: &CliArgs) -> Result<i32> {
+    let [baseline, listing] = cli_args.inputs.as_slice() else {
+        return Err(invalid_input("check expects a baseline and a listing".to_string()));
+    };
+    if cli_args.thresholds.is_empty() {
+        return Err(invalid_input("check needs at least one of --max-change, --max-key-change, --max-unmatched or --min-key-similarity".to_string()));
+    }
+    if baseline.is_dir() && listing.is_dir() {
+        if cli_args.sources.is_some() {
+            return Err(invalid_input("--source1 and --source2 need exactly two listings".to_string()));
+        }
+        return run_check_batch(baseline, listing, cli_args);
+    }
+
+    let pair = compare_pair(baseline, listing, cli_args)?;
+    println!("File Name: {}", pair.file_name);
+    for row in pair.comparison.summary_rows() {
+        println!("{:<22} {:>12} {:>12}", row.scope, row.distance, row.change);
+    }
+
+    if let Some(path) = &cli_args.junit {
+        let source_lines = HashMap::new();
+        let report = Report {
+            file_name: &pair.file_name,
+            comparison: &pair.comparison,
+            listing1: &pair.listing1,
+            listing2: &pair.listing2,
+            source_lines: &source_lines,
+        };
+        fs::write(path, pair_junit(&report, &cli_args.thresholds))?;
+    }
+
+    let violations = check_comparison(&pair.comparison, &cli_args.thresholds);
+    if violations.is_empty() {
+        println!("PASS");
+        return Ok(0);
+    }
+    println!("FAIL");
+    print_violations(&violations);
+    Ok(EXIT_THRESHOLD_EXCEEDED)
+}
+
+/// Saves the parsed listing with its compiler & flags as a snapshot that can replace the `.s` file as a baseline
+fn run_snapshot(cli_args: &CliArgs) -> Result<()> {
+    let [listing, output] = cli_args.inputs.as_slice() else {
+        return Err(invalid_input("snapshot expects a listing and an output file".to_string()));
+    };
+
+    let snapshot = create_snapshot(
+        listing,
+        cli_args.snapshot_compiler.clone(),
+        cli_args.snapshot_flags.clone(),
+        &cli_args.options.parser,
+    )?;
+    write_snapshot(&snapshot, output)?;
+    println!(
+        "Snapshot of {} ({} keys) saved to {}",
+        snapshot.file_name.as_deref().unwrap_or("unknown file"),
+        snapshot.blocks.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Builds a file at two git revisions & compares the listings with source line remapping across the revisions
+fn run_git(cli_args: &CliArgs) -> Result<()> {
+    let [repo, revision_a, revision_b] = cli_args.inputs.as_slice() else {
+        return Err(invalid_input("git expects a repository and two revisions".to_string()));
+    };
+    let Some(file) = &cli_args.file else {
+        return Err(invalid_input("git needs --file".to_string()));
+    };
+
+    let revisions = build_revisions(
+        repo,
+        &revision_a.to_string_lossy(),
+        &revision_b.to_string_lossy(),
+        file,
+        &cli_args.command,
+    )?;
+    let pair_args = CliArgs {
+        inputs: Vec::new(),
+        sources: Some((revisions.source_a.clone(), revisions.source_b.clone())),
+        source_root: Some(revisions.source_root()),
+        ..cli_args.clone()
+    };
+    run_pair(&revisions.listing_a, &revisions.listing_b, &pair_args).map(|_| ())
+}
+
+/// Configuration given with `--config`, or the `dissimetrics.toml` found from the working directory, or the defaults
+fn load_cli_config(args: &[String]) -> Result<Config> {
+    if let Some(index) = args.iter().position(|arg| arg == "--config") {
+        let path = parse_value::<PathBuf>("--config", args.get(index + 1))?;
+        return load_config(&path);
+    }
+    match std::env::current_dir().ok().and_then(|dir| find_config(&dir)) {
+        Some(path) => load_config(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Uses the default inputs of the configuration when no file is given
+fn with_config_inputs(mut cli_args: CliArgs, config: &Config) -> CliArgs {
+    if let (true, Some(file1), Some(file2)) = (cli_args.inputs.is_empty(), &config.inputs.file1, &config.inputs.file2) {
+        cli_args.inputs = vec![file1.clone(), file2.clone()];
+    }
+    cli_args
+}
+
+/// Runs a comparison without the GUI & prints the tables to stdout
+///
+/// Two directories are compared in batch mode, every listing pair is compared & rolled up into project totals.
+/// Three or more files are compared pairwise into a distance matrix. The `compile` command builds the two
+/// listings from a source file first, the `project` command builds both trees from a `compile_commands.json` &
+/// the `git` command builds a file at two revisions of a repository. The `check` command compares a listing
+/// against its baseline as a regression gate & the `snapshot` command saves a baseline. Snapshot files are
+/// accepted wherever a listing is. Options not given fall back to the configuration file. Returns the process
+/// exit code.
+pub fn run(args: &[String]) -> Result<i32> {
+    let config = load_cli_config(args)?;
+    let parse = |args: &[String]| parse_args(args, &config);
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("compile") => run_compile(&parse(&args[1..])?),
+        Some("project") => run_project(&parse(&args[1..])?),
+        Some("git") => run_git(&parse(&args[1..])?),
+        Some("snapshot") => run_snapshot(&parse(&args[1..])?),
+        Some("check") => return run_check(&with_config_inputs(parse(&args[1..])?, &config)),
+        _ => run_compare(&with_config_inputs(parse(args)?, &config)),
+    };
+    result.map(|_| 0)
+}
@@ -0,0 +1,590 @@
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classes::*,
+    cosine_similarties::*,
+    diff::*,
+    edit_costs::*,
+    histogram::*,
+    levenstein::*,
+    parser::{Listing, ParserOptions},
+    similarity::*,
+};
+
+/// State of a single key after comparing both listings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyStatus {
+    Compared,
+    /// Distance is known to be above the configured maximum, the exact value was not computed
+    OverThreshold,
+    OnlyInFile1,
+    OnlyInFile2,
+}
+
+/// Metrics of a single source reference key
+#[derive(Debug, Clone)]
+pub struct KeyResult {
+    pub key: String,
+    pub status: KeyStatus,
+    pub distance: i32,
+    pub block_length: i32,
+    pub similarity: f64,
+    pub change_percent: f64,
+    /// Jensen-Shannon divergence of the opcode histograms of both blocks
+    pub divergence: f64,
+    /// Edit distance between the instruction class sequences of both blocks, -1 when it was not computed
+    pub class_distance: i32,
+    /// Change of the instruction class counts from File 1 to File 2, empty when it was not computed
+    pub class_changes: String,
+    /// Edit distance over the instructions with the costs of `CompareOptions::edit_costs`, -1 when it was not computed
+    pub weighted_distance: f64,
+    /// Unchanged instructions, hunks & deleted plus inserted instructions of a diff of both blocks, -1 when the diff
+    /// was not computed
+    pub lcs_length: i32,
+    pub hunks: i32,
+    pub changed_lines: i32,
+    /// Damerau-Levenshtein distances over the instructions, with adjacent swaps only & unrestricted, -1 when they
+    /// were not computed
+    pub osa_distance: i32,
+    pub damerau_distance: i32,
+    /// Edits saved by swapping adjacent instructions, the instruction-level Levenshtein minus `osa_distance`
+    pub swaps: i32,
+    /// Every metric as a 0–1 similarity, -1 for the metrics that were not computed
+    pub similarities: Similarities,
+}
+
+impl KeyResult {
+    /// Result of a key that exists in only one listing, `block_length` is the length of the block that exists
+    fn missing(key: &str, status: KeyStatus, block_length: usize) -> Self {
+        KeyResult {
+            key: key.to_string(),
+            status,
+            distance: -1, // Indicate missing value with a sentinel
+            block_length: block_length as i32,
+            similarity: -1.0,
+            change_percent: -1.0,
+            divergence: -1.0,
+            class_distance: -1,
+            class_changes: String::new(),
+            weighted_distance: -1.0,
+            lcs_length: -1,
+            hunks: -1,
+            changed_lines: -1,
+            osa_distance: -1,
+            damerau_distance: -1,
+            swaps: -1,
+            similarities: Similarities::default(),
+        }
+    }
+
+    pub fn is_matched(&self) -> bool {
+        matches!(self.status, KeyStatus::Compared | KeyStatus::OverThreshold)
+    }
+
+    /// Distance as shown to the user, `> max` when the exact value was skipped
+    pub fn distance_label(&self) -> String {
+        match self.status {
+            KeyStatus::Compared => format!("{}", self.distance),
+            KeyStatus::OverThreshold => format!("> {}", self.distance - 1),
+            _ => "N/A".to_string(),
+        }
+    }
+
+    pub fn similarity_label(&self) -> String {
+        if self.is_matched() {
+            format!("{:.2}", self.similarity)
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    pub fn divergence_label(&self) -> String {
+        if self.is_matched() {
+            format!("{:.3}", self.divergence)
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    pub fn class_distance_label(&self) -> String {
+        if self.is_matched() && self.class_distance >= 0 {
+            format!("{}", self.class_distance)
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    pub fn weighted_distance_label(&self) -> String {
+        if self.is_matched() && self.weighted_distance >= 0.0 {
+            format!("{:.2}", self.weighted_distance)
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    /// A count of a matched key such as `hunks`, N/A for keys in only one listing & counts that were not computed
+    pub fn count_label(&self, count: i32) -> String {
+        if self.is_matched() && count >= 0 {
+            format!("{}", count)
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    /// Normalized similarity as shown to the user, an upper bound when the exact Levenshtein distance was skipped
+    pub fn similarity_of_label(&self, metric: SimilarityMetric) -> String {
+        let similarity = self.similarities.get(metric);
+        match self.status {
+            KeyStatus::OverThreshold if metric == SimilarityMetric::Levenshtein => format!("≤ {:.3}", similarity),
+            _ if self.is_matched() && similarity >= 0.0 => format!("{:.3}", similarity),
+            _ => "N/A".to_string(),
+        }
+    }
+
+    /// Change percentage as shown to the user, a lower bound when the exact distance was skipped
+    pub fn change_label(&self) -> String {
+        match self.status {
+            KeyStatus::Compared => format!("{:.2}%", self.change_percent),
+            KeyStatus::OverThreshold => format!("> {:.2}%", self.change_percent),
+            KeyStatus::OnlyInFile1 => "Only in File 1".to_string(),
+            KeyStatus::OnlyInFile2 => "Only in File 2".to_string(),
+        }
+    }
+}
+
+/// Options controlling how the listings are compared
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions {
+    /// Skip the exact Levenshtein distance for blocks that need more than this many edits
+    pub max_distance: Option<usize>,
+    /// Number of worker threads for the per-key metrics, None uses every core
+    pub threads: Option<usize>,
+    /// How the listings are parsed before they are compared
+    pub parser: ParserOptions,
+    /// Costs of the weighted edit distance
+    pub edit_costs: EditCosts,
+    /// Metrics of `config::METRICS` that are not computed, their values keep the -1 sentinel
+    pub skipped_metrics: Vec<String>,
+}
+
+impl CompareOptions {
+    /// Whether the values of a metric of `config::METRICS` are computed
+    pub fn computes(&self, metric: &str) -> bool {
+        !self.skipped_metrics.iter().any(|skipped| skipped == metric)
+    }
+}
+
+/// Result of comparing two listings, per key & for the whole file
+///
+/// `levenshtein_distance`, `file_length` & `change_percentage` only cover keys present in both listings. The
+/// `overall_*` totals also count every unmatched block as a full insertion or deletion of its length.
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    pub results: Vec<KeyResult>,
+    pub levenshtein_distance: i32,
+    pub file_length: f64,
+    pub cosine_similarity: f64,
+    pub change_percentage: f64,
+    /// Jensen-Shannon divergence of the opcode histograms of the whole listings
+    pub divergence: f64,
+    /// Sum of the class distances of the keys in both listings
+    pub class_distance: i32,
+    /// Change of the instruction class counts of the whole listings
+    pub class_changes: String,
+    /// Sum of the weighted distances of the keys in both listings
+    pub weighted_distance: f64,
+    /// Weighted total that also deletes or inserts every instruction of the unmatched blocks
+    pub overall_weighted_distance: f64,
+    /// Diff totals of the keys in both listings
+    pub lcs_length: i32,
+    pub hunks: i32,
+    pub changed_lines: i32,
+    /// Damerau-Levenshtein totals of the keys in both listings
+    pub osa_distance: i32,
+    pub damerau_distance: i32,
+    pub swaps: i32,
+    /// Similarities of the matched keys, weighted by their block length
+    pub similarities: Similarities,
+    /// Similarities of every key, unmatched blocks count as `Similarities::UNRELATED`
+    pub overall_similarities: Similarities,
+    /// Number of keys whose distance only contributes the lower bound `max_distance + 1` to the totals
+    pub over_threshold: usize,
+    pub matched_keys: usize,
+    pub unmatched_keys: usize,
+    pub overall_distance: i32,
+    pub overall_length: f64,
+    pub overall_change_percentage: f64,
+}
+
+/// Row of the summary table, already formatted for display
+#[derive(Debug, Clone)]
+pub struct SummaryRow {
+    pub scope: String,
+    pub distance: String,
+    pub weighted_distance: String,
+    pub change: String,
+    pub similarities: Similarities,
+    pub derivation: String,
+}
+
+/// Change percentage of a total, 0 when there is nothing to compare
+pub fn percentage(distance: f64, length: f64) -> f64 {
+    if length > 0.0 {
+        (distance / length) * 100.0
+    } else {
+        0.0
+    }
+}
+
+impl Comparison {
+    /// Matched-only & overall totals together with how each of them was computed
+    pub fn summary_rows(&self) -> Vec<SummaryRow> {
+        let total_prefix = if self.over_threshold > 0 { ">= " } else { "" };
+        let over_threshold_note = if self.over_threshold > 0 {
+            format!(
+                ", {} block(s) over the maximum distance count as max + 1 & are left out of the instruction-level metrics",
+                self.over_threshold
+            )
+        } else {
+            String::new()
+        };
+
+        vec![
+            SummaryRow {
+                scope: format!("Matched keys ({})", self.matched_keys),
+                distance: format!("{}{}", total_prefix, self.levenshtein_distance),
+                weighted_distance: format!("{}{:.2}", total_prefix, self.weighted_distance),
+                change: format!("{}{:.2}%", total_prefix, self.change_percentage),
+                similarities: self.similarities,
+                derivation: format!(
+                    "sum of distances / sum of max block lengths ({}) over keys in both files{}",
+                    self.file_length, over_threshold_note
+                ),
+            },
+            SummaryRow {
+                scope: format!("All keys ({})", self.matched_keys + self.unmatched_keys),
+                distance: format!("{}{}", total_prefix, self.overall_distance),
+                weighted_distance: format!("{}{:.2}", total_prefix, self.overall_weighted_distance),
+                change: format!("{}{:.2}%", total_prefix, self.overall_change_percentage),
+                similarities: self.overall_similarities,
+                derivation: format!(
+                    "matched totals + {} unmatched block(s) counted as full insertion/deletion of their length ({})",
+                    self.unmatched_keys,
+                    self.overall_length - self.file_length
+                ),
+            },
+        ]
+    }
+}
+
+/// Computes the metrics of two blocks that exist in both listings
+///
+/// The instruction-level metrics are only computed for the enabled metrics & are skipped when the blocks are over
+/// the maximum distance, they are O(N·M) in the instruction counts.
+pub fn compare_block(key: &str, value1: &str, value2: &str, options: &CompareOptions) -> KeyResult {
+    let max_file_len = value1.len().max(value2.len()) as f64;
+
+    let (status, distance) = match options.max_distance {
+        Some(max) => match levenshtein_bounded(value1.as_bytes(), value2.as_bytes(), max) {
+            Some(distance) => (KeyStatus::Compared, distance),
+            None => (KeyStatus::OverThreshold, max + 1),
+        },
+        None => (KeyStatus::Compared, optimized_levenshtein(value1.as_bytes(), value2.as_bytes())),
+    };
+
+    let (padded_str1, padded_str2) = pad_strings(value1, value2);
+    let similarity = cosine_similarity(&padded_str1, &padded_str2);
+    let change_percent = percentage(distance as f64, max_file_len);
+    let (histogram1, histogram2) = (block_histogram(value1), block_histogram(value2));
+    let divergence = jensen_shannon_divergence(&histogram1, &histogram2);
+    let dialect = options.parser.dialect;
+    let exact = status == KeyStatus::Compared;
+    let classes = exact && options.computes("classes");
+    let class_distance = classes.then(|| class_distance(value1, value2, dialect));
+    let weighted = (exact && options.computes("weighted"))
+        .then(|| weighted_distance(value1, value2, &options.edit_costs, dialect));
+
+    let lines1: Vec<&str> = value1.lines().collect();
+    let lines2: Vec<&str> = value2.lines().collect();
+    let diff = (exact && options.computes("diff")).then(|| DiffStats::new(&myers_diff(&lines1, &lines2)));
+    let longest = lines1.len().max(lines2.len()) as f64;
+    let reordering = (exact && options.computes("damerau"))
+        .then(|| (osa_distance(&lines1, &lines2), damerau_levenshtein(&lines1, &lines2)));
+
+    let instructions = |histogram: &Histogram| histogram.values().sum::<usize>();
+    let delete_all = |block: &str| deletion_cost(block, &options.edit_costs, dialect);
+    let similarities = Similarities {
+        levenshtein: normalized_similarity(distance as f64, max_file_len),
+        cosine: angular_similarity(similarity, value1, value2),
+        divergence: 1.0 - divergence,
+        classes: class_distance.map_or(-1.0, |distance| {
+            normalized_similarity(distance as f64, instructions(&histogram1).max(instructions(&histogram2)) as f64)
+        }),
+        weighted: weighted.map_or(-1.0, |weighted| {
+            normalized_similarity(weighted, delete_all(value1).max(delete_all(value2)))
+        }),
+        diff: diff.map_or(-1.0, |diff| normalized_similarity(longest - diff.lcs_length as f64, longest)),
+        damerau: reordering.map_or(-1.0, |(_, damerau)| normalized_similarity(damerau as f64, longest)),
+    };
+
+    KeyResult {
+        key: key.to_string(),
+        status,
+        distance: distance as i32,
+        block_length: max_file_len as i32,
+        similarity,
+        change_percent,
+        divergence,
+        class_distance: class_distance.map_or(-1, |distance| distance as i32),
+        class_changes: if classes {
+            class_changes(&class_histogram(&histogram1, dialect), &class_histogram(&histogram2, dialect))
+        } else {
+            String::new()
+        },
+        weighted_distance: weighted.unwrap_or(-1.0),
+        lcs_length: diff.map_or(-1, |diff| diff.lcs_length as i32),
+        hunks: diff.map_or(-1, |diff| diff.hunks as i32),
+        changed_lines: diff.map_or(-1, |diff| diff.changed_lines as i32),
+        osa_distance: reordering.map_or(-1, |(osa, _)| osa.osa as i32),
+        damerau_distance: reordering.map_or(-1, |(_, damerau)| damerau as i32),
+        swaps: reordering.map_or(-1, |(osa, _)| (osa.levenshtein - osa.osa) as i32),
+        similarities,
+    }
+}
+
+/// Sort key of a `file:line` key so that lines are ordered numerically within a file
+pub fn key_order(key: &str) -> (&str, u32) {
+    match key.rsplit_once(':') {
+        Some((file, line)) => (file, line.parse().unwrap_or(u32::MAX)),
+        None => (key, u32::MAX),
+    }
+}
+
+/// Column the per-key table is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SortColumn {
+    #[default]
+    Key,
+    Distance,
+    Similarity,
+    BlockLength,
+    Change,
+    Divergence,
+    ClassDistance,
+    WeightedDistance,
+    Lcs,
+    Hunks,
+    ChangedLines,
+    Osa,
+    Damerau,
+    Swaps,
+    Normalized(SimilarityMetric),
+}
+
+/// Results in the order of the given column, ties & unmatched keys keep the file & line order
+pub fn sorted_results(results: &[KeyResult], column: SortColumn, ascending: bool) -> Vec<&KeyResult> {
+    let mut sorted: Vec<&KeyResult> = results.iter().collect();
+    sorted.sort_by(|a, b| {
+        let order = match column {
+            SortColumn::Key => key_order(&a.key).cmp(&key_order(&b.key)),
+            SortColumn::Distance => a.distance.cmp(&b.distance),
+            SortColumn::Similarity => a.similarity.total_cmp(&b.similarity),
+            SortColumn::BlockLength => a.block_length.cmp(&b.block_length),
+            SortColumn::Change => a.change_percent.total_cmp(&b.change_percent),
+            SortColumn::Divergence => a.divergence.total_cmp(&b.divergence),
+            SortColumn::ClassDistance => a.class_distance.cmp(&b.class_distance),
+            SortColumn::WeightedDistance => a.weighted_distance.total_cmp(&b.weighted_distance),
+            SortColumn::Lcs => a.lcs_length.cmp(&b.lcs_length),
+            SortColumn::Hunks => a.hunks.cmp(&b.hunks),
+            SortColumn::ChangedLines => a.changed_lines.cmp(&b.changed_lines),
+            SortColumn::Osa => a.osa_distance.cmp(&b.osa_distance),
+            SortColumn::Damerau => a.damerau_distance.cmp(&b.damerau_distance),
+            SortColumn::Swaps => a.swaps.cmp(&b.swaps),
+            SortColumn::Normalized(metric) => a.similarities.get(metric).total_cmp(&b.similarities.get(metric)),
+        };
+        if ascending {
+            order
+        } else {
+            order.reverse()
+        }
+    });
+    sorted
+}
+
+/// Computes the result of a single key from whichever listings contain it
+///
+/// The class changes of an unmatched key list every instruction of its block as removed or added.
+fn compare_key(key: &str, listing1: &Listing, listing2: &Listing, options: &CompareOptions) -> KeyResult {
+    let classes = |block: Option<&String>| {
+        class_histogram(&block.map(|block| block_histogram(block)).unwrap_or_default(), options.parser.dialect)
+    };
+    let changes = |block1: Option<&String>, block2: Option<&String>| {
+        if options.computes("classes") {
+            class_changes(&classes(block1), &classes(block2))
+        } else {
+            String::new()
+        }
+    };
+    match (listing1.blocks.get(key), listing2.blocks.get(key)) {
+        (Some(value1), Some(value2)) => compare_block(key, value1, value2, options),
+        (Some(value1), None) => KeyResult {
+            class_changes: changes(Some(value1), None),
+            ..KeyResult::missing(key, KeyStatus::OnlyInFile1, value1.len())
+        },
+        (None, value2) => KeyResult {
+            class_changes: changes(None, value2),
+            ..KeyResult::missing(key, KeyStatus::OnlyInFile2, value2.map_or(0, |value| value.len()))
+        },
+    }
+}
+
+/// Compares every source reference block of the two listings
+///
+/// Keys are independent of each other so they are compared in parallel, the results are ordered by file & line
+/// number regardless of the thread count. Keys present in only one of the listings are reported with the
+/// `OnlyInFile1` / `OnlyInFile2` status & only contribute to the overall totals.
+pub fn compare_listings(listing1: &Listing, listing2: &Listing, options: &CompareOptions) -> Comparison {
+    let unique_keys: BTreeSet<&String> = listing1.blocks.keys().chain(listing2.blocks.keys()).collect();
+    let mut keys: Vec<&String> = unique_keys.into_iter().collect();
+    keys.sort_by(|a, b| key_order(a).cmp(&key_order(b)));
+
+    let compare_all = || -> Vec<KeyResult> {
+        keys.par_iter().map(|key| compare_key(key, listing1, listing2, options)).collect()
+    };
+    let pool = options
+        .threads
+        .and_then(|threads| rayon::ThreadPoolBuilder::new().num_threads(threads).build().ok());
+    let results = match pool {
+        Some(pool) => pool.install(compare_all),
+        None => compare_all(),
+    };
+
+    let mut comparison = Comparison::default();
+    for result in &results {
+        if result.is_matched() {
+            comparison.matched_keys += 1;
+            comparison.levenshtein_distance += result.distance;
+            // Skipped metrics hold -1
+            comparison.class_distance += result.class_distance.max(0);
+            comparison.weighted_distance += result.weighted_distance.max(0.0);
+            comparison.lcs_length += result.lcs_length.max(0);
+            comparison.hunks += result.hunks.max(0);
+            comparison.changed_lines += result.changed_lines.max(0);
+            comparison.osa_distance += result.osa_distance.max(0);
+            comparison.damerau_distance += result.damerau_distance.max(0);
+            comparison.swaps += result.swaps.max(0);
+            comparison.file_length += result.block_length as f64;
+        } else {
+            // The whole block was inserted or deleted
+            let block = listing1.blocks.get(&result.key).or(listing2.blocks.get(&result.key));
+            let block = block.map_or("", |block| block.as_str());
+            if options.computes("weighted") {
+                comparison.overall_weighted_distance += deletion_cost(block, &options.edit_costs, options.parser.dialect);
+            }
+            comparison.unmatched_keys += 1;
+            comparison.overall_distance += result.block_length;
+            comparison.overall_length += result.block_length as f64;
+        }
+        if result.status == KeyStatus::OverThreshold {
+            comparison.over_threshold += 1;
+        }
+    }
+    comparison.results = results;
+    comparison.overall_distance += comparison.levenshtein_distance;
+    comparison.overall_length += comparison.file_length;
+    comparison.overall_weighted_distance += comparison.weighted_distance;
+    comparison.similarities = Similarities::weighted_mean(
+        comparison
+            .results
+            .iter()
+            .filter(|result| result.is_matched())
+            .map(|result| (&result.similarities, result.block_length as f64)),
+    );
+    comparison.overall_similarities = Similarities::weighted_mean(comparison.results.iter().map(|result| {
+        let similarities = if result.is_matched() { &result.similarities } else { &Similarities::UNRELATED };
+        (similarities, result.block_length as f64)
+    }));
+
+    let (padded_str1, padded_str2) = pad_strings(&listing1.text, &listing2.text);
+    comparison.cosine_similarity = cosine_similarity(&padded_str1, &padded_str2);
+    let (histogram1, histogram2) = (listing_histogram(listing1), listing_histogram(listing2));
+    comparison.divergence = jensen_shannon_divergence(&histogram1, &histogram2);
+    let dialect = options.parser.dialect;
+    if options.computes("classes") {
+        comparison.class_changes = class_changes(&class_histogram(&histogram1, dialect), &class_histogram(&histogram2, dialect));
+    }
+    comparison.change_percentage = percentage(comparison.levenshtein_distance as f64, comparison.file_length);
+    comparison.overall_change_percentage = percentage(comparison.overall_distance as f64, comparison.overall_length);
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(blocks: &[(&str, &str)]) -> Listing {
+        let blocks = blocks.iter().map(|(key, block)| (key.to_string(), block.to_string())).collect();
+        Listing { file_name: Some("a.c".to_string()), blocks, text: String::new() }
+    }
+
+    #[test]
+    fn unmatched_keys_only_count_in_overall_totals() {
+        let listing1 = listing(&[("a.c:1", "movl\nret"), ("a.c:2", "addl"), ("a.c:10", "call")]);
+        let listing2 = listing(&[("a.c:1", "movq\nret"), ("a.c:3", "subl\nsubl")]);
+        let comparison = compare_listings(&listing1, &listing2, &CompareOptions::default());
+
+        let keys: Vec<&str> = comparison.results.iter().map(|result| result.key.as_str()).collect();
+        assert_eq!(keys, ["a.c:1", "a.c:2", "a.c:3", "a.c:10"]);
+        let statuses: Vec<KeyStatus> = comparison.results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            statuses,
+            [KeyStatus::Compared, KeyStatus::OnlyInFile1, KeyStatus::OnlyInFile2, KeyStatus::OnlyInFile1]
+        );
+        assert_eq!(comparison.results[1].distance, -1);
+        assert_eq!(comparison.results[2].block_length, 9);
+
+        assert_eq!(comparison.matched_keys, 1);
+        assert_eq!(comparison.unmatched_keys, 3);
+        assert_eq!(comparison.levenshtein_distance, 1);
+        assert_eq!(comparison.file_length, 8.0);
+        assert_eq!(comparison.change_percentage, 12.5);
+        // 1 matched edit plus the unmatched blocks of 4 + 9 + 4 bytes
+        assert_eq!(comparison.overall_distance, 18);
+        assert_eq!(comparison.overall_length, 25.0);
+        assert_eq!(comparison.overall_change_percentage, 72.0);
+    }
+
+    #[test]
+    fn over_threshold_keys_count_as_max_plus_one() {
+        let listing1 = listing(&[("a.c:1", "movl\nret"), ("a.c:2", "addl")]);
+        let listing2 = listing(&[("a.c:1", "xorps\ncall"), ("a.c:2", "addl")]);
+        let options = CompareOptions { max_distance: Some(2), ..Default::default() };
+        let comparison = compare_listings(&listing1, &listing2, &options);
+
+        assert_eq!(comparison.results[0].status, KeyStatus::OverThreshold);
+        assert_eq!(comparison.results[0].distance, 3);
+        assert_eq!(comparison.results[1].status, KeyStatus::Compared);
+        assert_eq!(comparison.matched_keys, 2);
+        assert_eq!(comparison.over_threshold, 1);
+        assert_eq!(comparison.levenshtein_distance, 3);
+        assert_eq!(comparison.overall_distance, 3);
+    }
+
+    #[test]
+    fn empty_listings_have_no_change() {
+        let comparison = compare_listings(&Listing::default(), &Listing::default(), &CompareOptions::default());
+        assert!(comparison.results.is_empty());
+        assert_eq!(comparison.change_percentage, 0.0);
+        assert_eq!(comparison.overall_change_percentage, 0.0);
+    }
+
+    #[test]
+    fn empty_blocks_have_no_change() {
+        let result = compare_block("a.c:1", "", "", &CompareOptions::default());
+        assert_eq!(result.distance, 0);
+        assert_eq!(result.change_percent, 0.0);
+    }
+}
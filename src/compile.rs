@@ -0,0 +1,336 @@
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    io::{Error, ErrorKind, Result},
+    path::{Component, Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Compiler used when none is chosen
+pub const DEFAULT_COMPILER: &str = "gcc";
+
+/// Compiler executable & flags used to build one side of the comparison
+#[derive(Debug, Clone)]
+pub struct CompileSide {
+    pub compiler: String,
+    pub flags: Vec<String>,
+}
+
+/// Splits a flag string such as `-O2 -march=native` into separate arguments
+pub fn split_flags(flags: &str) -> Vec<String> {
+    flags.split_whitespace().map(|flag| flag.to_string()).collect()
+}
+
+/// Lists the C/C++ compilers found on PATH, e.g. `gcc`, `g++-12` or `clang++`
+pub fn find_compilers() -> Vec<String> {
+    let re = Regex::new(r"^(gcc|g\+\+|cc|c\+\+|clang|clang\+\+)(-\d+(\.\d+)*)?$").unwrap();
+    let mut compilers = BTreeSet::new();
+
+    if let Some(path) = env::var_os("PATH") {
+        for dir in env::split_paths(&path) {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if re.is_match(&name) && entry.path().is_file() {
+                    compilers.insert(name);
+                }
+            }
+        }
+    }
+    compilers.into_iter().collect()
+}
+
+/// Directory of generated listings that is removed with everything in it when dropped
+///
+/// Keep it alive for as long as the listings are compared or shown.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Creates an empty directory for the generated listings under the system temp directory
+///
+/// The path is absolute, the compilers run in other directories.
+pub fn create_temp_dir(prefix: &str) -> Result<TempDir> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos()).unwrap_or(0);
+    let dir = env::temp_dir().join(format!("dissimetrics-{}-{}-{}", prefix, std::process::id(), nanos));
+    fs::create_dir_all(&dir)?;
+    Ok(TempDir { path: fs::canonicalize(dir)? })
+}
+
+/// Runs a command & turns a non-zero exit status into an error carrying its error output
+pub fn run_command(mut command: Command) -> Result<()> {
+    let output = command
+        .output()
+        .map_err(|error| Error::new(error.kind(), format!("Failed to run {:?}: {}", command.get_program(), error)))?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "{:?} failed: {}",
+            command.get_program(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Compiles a source file to an assembly listing with `-S -fverbose-asm`
+///
+/// The compiler runs in the directory of the source file & gets the bare file name, so the `.file` directive &
+/// the source references of the listing are the same for every side.
+pub fn compile_to_listing(source: &Path, side: &CompileSide, output: &Path) -> Result<()> {
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Not a source file: {}", source.display())))?;
+
+    let mut command = Command::new(&side.compiler);
+    command.args(&side.flags).args(["-S", "-fverbose-asm", "-o"]).arg(output).arg(file_name);
+    if let Some(dir) = source.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        command.current_dir(dir);
+    }
+    run_command(command)
+}
+
+/// Compiles the source once per side into a new temp directory & returns it with the two listings
+///
+/// The listings are removed together with the directory.
+pub fn compile_pair(source: &Path, side_a: &CompileSide, side_b: &CompileSide) -> Result<(TempDir, PathBuf, PathBuf)> {
+    let dir = create_temp_dir("compile")?;
+    let stem = source.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+
+    let listing_a = dir.path().join(format!("{}_a.s", stem));
+    let listing_b = dir.path().join(format!("{}_b.s", stem));
+    compile_to_listing(source, side_a, &listing_a)?;
+    compile_to_listing(source, side_b, &listing_b)?;
+    Ok((dir, listing_a, listing_b))
+}
+
+/// One translation unit of a `compile_commands.json` compilation database
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompileCommand {
+    pub directory: PathBuf,
+    pub file: PathBuf,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub arguments: Option<Vec<String>>,
+}
+
+impl CompileCommand {
+    /// Compiler & arguments, from `arguments` or split from the `command` string
+    pub fn argv(&self) -> Vec<String> {
+        match (&self.arguments, &self.command) {
+            (Some(arguments), _) => arguments.clone(),
+            (None, Some(command)) => split_command_line(command),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Absolute path of the source file, with `..` & `.` resolved
+    pub fn source_path(&self) -> PathBuf {
+        let mut path = PathBuf::new();
+        for component in self.directory.join(&self.file).components() {
+            match component {
+                Component::ParentDir => {
+                    path.pop();
+                }
+                Component::CurDir => {}
+                component => path.push(component),
+            }
+        }
+        path
+    }
+}
+
+/// Reads the entries of a `compile_commands.json` file
+pub fn read_compile_commands(path: &Path) -> Result<Vec<CompileCommand>> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|error| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), error)))
+}
+
+/// Splits a shell command line into arguments, handling single & double quotes and backslash escapes
+pub fn split_command_line(command: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_argument = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_argument = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+    if in_argument {
+        arguments.push(current);
+    }
+    arguments
+}
+
+/// Turns a compile command into one that writes a verbose assembly listing to `output`
+///
+/// The original output, `-c`, `-S` & the dependency file options such as `-MD` or `-MF <file>` are dropped, then
+/// `-S -fverbose-asm`, the extra flags & the new output are appended. Later flags win for gcc & clang, so the extra
+/// flags override e.g. the optimization level.
+pub fn listing_arguments(argv: &[String], extra_flags: &[String], output: &Path) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut iter = argv.iter();
+    while let Some(argument) = iter.next() {
+        match argument.as_str() {
+            // Options followed by a separate value
+            "-o" | "--output" | "-MF" | "-MT" | "-MQ" => {
+                iter.next();
+            }
+            "-c" | "-S" | "-MD" | "-MMD" | "-MP" => {}
+            // Joined forms such as `-ofile`, `--output=file` or `-MFfile.d`
+            joined if ["-o", "--output=", "-MF", "-MT", "-MQ"].iter().any(|prefix| joined.starts_with(prefix)) => {}
+            _ => arguments.push(argument.clone()),
+        }
+    }
+    arguments.extend(["-S".to_string(), "-fverbose-asm".to_string()]);
+    arguments.extend(extra_flags.iter().cloned());
+    arguments.extend(["-o".to_string(), output.to_string_lossy().to_string()]);
+    arguments
+}
+
+/// Deepest directory containing every given path
+fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let mut ancestor = match paths.first().and_then(|path| path.parent()) {
+        Some(parent) => parent.to_path_buf(),
+        None => return PathBuf::new(),
+    };
+    for path in paths {
+        while !path.starts_with(&ancestor) {
+            if !ancestor.pop() {
+                return PathBuf::new();
+            }
+        }
+    }
+    ancestor
+}
+
+/// Listing trees generated from a compilation database
+#[derive(Debug)]
+pub struct ProjectListings {
+    /// Temp directory holding both trees, they are removed when it is dropped
+    pub dir: TempDir,
+    pub dir_a: PathBuf,
+    pub dir_b: PathBuf,
+    /// Translation units that failed to compile, with the compiler output
+    pub errors: Vec<String>,
+}
+
+/// Re-runs every translation unit of a `compile_commands.json` with both flag deltas
+///
+/// The listings are written to two temp trees that mirror the source layout, ready for `compare_directories`.
+/// Translation units are compiled in parallel & a failing one is reported in `errors` without stopping the others.
+pub fn compile_project(compile_commands: &Path, flags_a: &[String], flags_b: &[String]) -> Result<ProjectListings> {
+    let commands = read_compile_commands(compile_commands)?;
+    let sources: Vec<PathBuf> = commands.iter().map(|command| command.source_path()).collect();
+    let root = common_ancestor(&sources);
+
+    let dir = create_temp_dir("project")?;
+    let dir_a = dir.path().join("a");
+    let dir_b = dir.path().join("b");
+
+    let errors: Vec<String> = commands
+        .par_iter()
+        .zip(sources.par_iter())
+        .flat_map_iter(|(command, source)| {
+            let relative = source.strip_prefix(&root).unwrap_or(source);
+            let argv = command.argv();
+            let mut errors = Vec::new();
+
+            for (side_dir, flags) in [(&dir_a, flags_a), (&dir_b, flags_b)] {
+                let output = side_dir.join(format!("{}.s", relative.to_string_lossy()));
+                let result = match argv.split_first() {
+                    Some((compiler, arguments)) => output
+                        .parent()
+                        .map_or(Ok(()), fs::create_dir_all)
+                        .and_then(|_| {
+                            let mut process = Command::new(compiler);
+                            process.args(listing_arguments(arguments, flags, &output)).current_dir(&command.directory);
+                            run_command(process)
+                        }),
+                    None => Err(Error::new(ErrorKind::InvalidData, "empty compile command")),
+                };
+                if let Err(error) = result {
+                    errors.push(format!("{}: {}", source.display(), error));
+                }
+            }
+            errors
+        })
+        .collect();
+
+    Ok(ProjectListings { dir, dir_a, dir_b, errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        split_flags(line)
+    }
+
+    #[test]
+    fn listing_arguments_replace_the_output() {
+        let output = Path::new("/tmp/out.s");
+        for command in ["-O2 -c a.c -o a.o", "-O2 -c a.c -oa.o", "-O2 -c a.c --output=a.o", "-O2 -c a.c --output a.o"] {
+            assert_eq!(
+                listing_arguments(&args(command), &args("-O3"), output),
+                args("-O2 a.c -S -fverbose-asm -O3 -o /tmp/out.s"),
+                "{}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn listing_arguments_drop_dependency_files() {
+        let argv = args("-Iinclude -MD -MMD -MP -MF a.d -MT a.o -MQ $(OBJ) -MFb.d -MTb.o -MQc.o -DX=1 -c a.c");
+        assert_eq!(
+            listing_arguments(&argv, &[], Path::new("out.s")),
+            args("-Iinclude -DX=1 a.c -S -fverbose-asm -o out.s")
+        );
+    }
+}
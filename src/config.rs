@@ -0,0 +1,185 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check::Thresholds,
+    compare::CompareOptions,
+    edit_costs::EditCosts,
+    parser::ParserOptions,
+    similarity::SimilarityMetric,
+};
+
+/// Name of the configuration file looked up from the working directory upwards
+pub const CONFIG_FILE_NAME: &str = "dissimetrics.toml";
+
+/// Metrics that can be enabled in the configuration, in the order of the table columns
+pub const METRICS: [&str; 8] = [
+    "levenshtein",
+    "cosine",
+    "change",
+    "divergence",
+    "classes",
+    "weighted",
+    "diff",
+    "damerau",
+];
+
+/// Listings & sources used when none are given, relative paths are relative to the configuration file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputConfig {
+    pub file1: Option<PathBuf>,
+    pub file2: Option<PathBuf>,
+    pub source1: Option<PathBuf>,
+    pub source2: Option<PathBuf>,
+    pub source_root: Option<PathBuf>,
+}
+
+/// Metrics shown in the tables & the options used to compute them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: Vec<String>,
+    /// Also show every enabled metric as a 0–1 similarity
+    pub normalized: bool,
+    pub max_distance: Option<usize>,
+    pub threads: Option<usize>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: METRICS.iter().map(|metric| metric.to_string()).collect(),
+            normalized: false,
+            max_distance: None,
+            threads: None,
+        }
+    }
+}
+
+impl MetricsConfig {
+    pub fn is_enabled(&self, metric: &str) -> bool {
+        self.enabled.iter().any(|enabled| enabled == metric)
+    }
+
+    /// Similarities shown next to the raw values, none unless `normalized` is set
+    pub fn similarity_metrics(&self) -> Vec<SimilarityMetric> {
+        SimilarityMetric::ALL
+            .into_iter()
+            .filter(|metric| self.normalized && self.is_enabled(metric.name()))
+            .collect()
+    }
+
+    /// Turns a metric on or off, keeping the order of `METRICS`
+    pub fn set_enabled(&mut self, metric: &str, enabled: bool) {
+        let mut metrics: Vec<String> = self.enabled.iter().filter(|name| name.as_str() != metric).cloned().collect();
+        if enabled {
+            metrics.push(metric.to_string());
+        }
+        metrics.sort_by_key(|name| METRICS.iter().position(|known| known == name));
+        self.enabled = metrics;
+    }
+}
+
+/// Contents of a `dissimetrics.toml` project configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub inputs: InputConfig,
+    pub parser: ParserOptions,
+    pub metrics: MetricsConfig,
+    pub edit_costs: EditCosts,
+    pub thresholds: Thresholds,
+}
+
+/// Metrics that are neither enabled nor needed by a similarity threshold, they are left out of the comparison
+pub fn skipped_metrics(metrics: &MetricsConfig, thresholds: &Thresholds) -> Vec<String> {
+    let thresholded = |name: &str| {
+        SimilarityMetric::ALL
+            .iter()
+            .any(|metric| metric.name() == name && thresholds.min_key_similarity.get(*metric).is_some())
+    };
+    METRICS
+        .iter()
+        .filter(|name| !metrics.is_enabled(name) && !thresholded(name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+impl Config {
+    pub fn compare_options(&self) -> CompareOptions {
+        CompareOptions {
+            max_distance: self.metrics.max_distance,
+            threads: self.metrics.threads,
+            parser: self.parser.clone(),
+            edit_costs: self.edit_costs.clone(),
+            skipped_metrics: skipped_metrics(&self.metrics, &self.thresholds),
+        }
+    }
+
+    /// Input paths of the configuration, each one passed through `map`
+    fn map_inputs(&mut self, map: impl Fn(&Path) -> PathBuf) {
+        let inputs = &mut self.inputs;
+        for path in [&mut inputs.file1, &mut inputs.file2, &mut inputs.source1, &mut inputs.source2, &mut inputs.source_root]
+            .into_iter()
+            .flatten()
+        {
+            *path = map(path);
+        }
+    }
+}
+
+/// Looks for the configuration file in the directory & each of its parents
+pub fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().map(|dir| dir.join(CONFIG_FILE_NAME)).find(|path| path.is_file())
+}
+
+/// Reads a configuration file & makes its input paths absolute
+pub fn load_config(path: &Path) -> Result<Config> {
+    let content = fs::read_to_string(path)?;
+    let mut config: Config = toml::from_str(&content)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), error)))?;
+
+    if let Some(unknown) = config.metrics.enabled.iter().find(|metric| !METRICS.contains(&metric.as_str())) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{}: unknown metric {}, expected one of {}", path.display(), unknown, METRICS.join(", ")),
+        ));
+    }
+
+    if let Some(cost) = config.edit_costs.negative_cost() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{}: edit cost {} must not be negative", path.display(), cost),
+        ));
+    }
+
+    let out_of_range = SimilarityMetric::ALL.into_iter().find(|metric| {
+        config.thresholds.min_key_similarity.get(*metric).is_some_and(|limit| !(0.0..=1.0).contains(&limit))
+    });
+    if let Some(metric) = out_of_range {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{}: minimum {} similarity must be between 0 and 1", path.display(), metric.name()),
+        ));
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    config.map_inputs(|input| dir.join(input));
+    Ok(config)
+}
+
+/// Writes the configuration, input paths inside the directory of the file are stored relative to it
+pub fn save_config(config: &Config, path: &Path) -> Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let mut config = config.clone();
+    config.map_inputs(|input| input.strip_prefix(&dir).unwrap_or(input).to_path_buf());
+
+    let content = toml::to_string_pretty(&config).map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+    fs::write(path, content)
+}
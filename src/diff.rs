@@ -0,0 +1,310 @@
+/// Single step of an edit script turning `old` into `new`
+///
+/// Indexes are 0 based positions in the respective sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffOp {
+    Equal { old: usize, new: usize },
+    Delete { old: usize },
+    Insert { new: usize },
+}
+
+/// Computes the shortest edit script between two sequences with Myers' O((N+M)D) algorithm
+///
+/// The returned operations cover every element of both sequences in order, so the `Equal` steps form a longest
+/// common subsequence. Uses the linear space variant, the middle snake of each range splits it in two, so only the
+/// script itself & two diagonal vectors are kept instead of one vector per edit.
+pub fn myers_diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    let size = 2 * (old.len() + new.len()).div_ceil(2) + 3;
+    let mut forward = vec![0isize; size];
+    let mut backward = vec![0isize; size];
+    diff_range(old, new, (0, 0), &mut forward, &mut backward, &mut ops);
+    ops
+}
+
+/// Appends the edit script of `old` to `new`, `start` is the position of both ranges in the full sequences
+fn diff_range<T: PartialEq>(
+    mut old: &[T],
+    mut new: &[T],
+    start: (usize, usize),
+    forward: &mut [isize],
+    backward: &mut [isize],
+    ops: &mut Vec<DiffOp>,
+) {
+    let (mut old_start, mut new_start) = start;
+    let equal = |ops: &mut Vec<DiffOp>, old: usize, new: usize, len: usize| {
+        ops.extend((0..len).map(|i| DiffOp::Equal { old: old + i, new: new + i }));
+    };
+
+    // Common prefix & suffix are part of every shortest script
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    equal(ops, old_start, new_start, prefix);
+    (old, new) = (&old[prefix..], &new[prefix..]);
+    (old_start, new_start) = (old_start + prefix, new_start + prefix);
+    let suffix = old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count();
+    (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() {
+        ops.extend((0..new.len()).map(|i| DiffOp::Insert { new: new_start + i }));
+    } else if new.is_empty() {
+        ops.extend((0..old.len()).map(|i| DiffOp::Delete { old: old_start + i }));
+    } else {
+        let (x_start, y_start, x_end, y_end) = middle_snake(old, new, forward, backward);
+        diff_range(&old[..x_start], &new[..y_start], (old_start, new_start), forward, backward, ops);
+        equal(ops, old_start + x_start, new_start + y_start, x_end - x_start);
+        diff_range(&old[x_end..], &new[y_end..], (old_start + x_end, new_start + y_end), forward, backward, ops);
+    }
+    equal(ops, old_start + old.len(), new_start + new.len(), suffix);
+}
+
+/// Start & end of a snake in the middle of a shortest path from the start to the end of both sequences
+///
+/// The path is searched from both ends at once. `forward[k]` holds the furthest x reached on diagonal `k = x − y`,
+/// `backward[k]` the same for the reversed sequences. Both sequences are non-empty & differ in the first & last
+/// element, so the snake never starts at the beginning or ends at the end & splitting there always makes progress.
+fn middle_snake<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    forward: &mut [isize],
+    backward: &mut [isize],
+) -> (usize, usize, usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = (forward.len() / 2) as isize;
+    let at = |k: isize| (k + offset) as usize;
+    forward[at(1)] = 0;
+    backward[at(1)] = 0;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)] // move down: insertion
+            } else {
+                forward[at(k - 1)] + 1 // move right: deletion
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            // The backward search has taken d − 1 steps, its diagonal `delta − k` meets this one
+            let reverse_k = delta - k;
+            if odd && (1 - d..d).contains(&reverse_k) && x + backward[at(reverse_k)] >= n {
+                return (x_start as usize, y_start as usize, x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && old[(n - 1 - x) as usize] == new[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            let forward_k = delta - k;
+            if !odd && (-d..=d).contains(&forward_k) && forward[at(forward_k)] + x >= n {
+                return ((n - x) as usize, (m - y) as usize, (n - x_start) as usize, (m - y_start) as usize);
+            }
+        }
+    }
+    unreachable!("the searches meet after at most (N + M) / 2 steps")
+}
+
+/// A run of consecutive deletions & insertions, the unchanged lines around it are not included
+///
+/// Starts are 0 based positions in the respective sequence, where the hunk begins or, for an empty side, where the
+/// lines of the other side are inserted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hunk {
+    /// Position in `ops` of the first step of the hunk
+    pub op_index: usize,
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+}
+
+impl Hunk {
+    /// Unified diff header with 1 based line numbers, e.g. `@@ -3,2 +3,1 @@`
+    pub fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@", self.old_start + 1, self.old_len, self.new_start + 1, self.new_len)
+    }
+}
+
+/// Groups the changed steps of an edit script into hunks
+pub fn hunks(ops: &[DiffOp]) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let (mut old, mut new) = (0, 0);
+    let mut in_hunk = false;
+    for (index, op) in ops.iter().enumerate() {
+        if let DiffOp::Equal { .. } = op {
+            old += 1;
+            new += 1;
+            in_hunk = false;
+            continue;
+        }
+        if !in_hunk {
+            hunks.push(Hunk { op_index: index, old_start: old, old_len: 0, new_start: new, new_len: 0 });
+            in_hunk = true;
+        }
+        if let Some(hunk) = hunks.last_mut() {
+            match op {
+                DiffOp::Delete { .. } => {
+                    hunk.old_len += 1;
+                    old += 1;
+                }
+                _ => {
+                    hunk.new_len += 1;
+                    new += 1;
+                }
+            }
+        }
+    }
+    hunks
+}
+
+/// Metrics of an edit script as an engineer reads a diff
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DiffStats {
+    /// Length of the longest common subsequence, the unchanged lines
+    pub lcs_length: usize,
+    pub hunks: usize,
+    /// Deleted plus inserted lines, a replaced line counts twice as in `diff --stat`
+    pub changed_lines: usize,
+}
+
+impl DiffStats {
+    pub fn new(ops: &[DiffOp]) -> Self {
+        let lcs_length = ops.iter().filter(|op| matches!(op, DiffOp::Equal { .. })).count();
+        DiffStats { lcs_length, hunks: hunks(ops).len(), changed_lines: ops.len() - lcs_length }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// LCS length with the textbook O(N·M) table
+    fn lcs_length(a: &[char], b: &[char]) -> usize {
+        let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                table[i][j] = if a[i - 1] == b[j - 1] {
+                    table[i - 1][j - 1] + 1
+                } else {
+                    table[i - 1][j].max(table[i][j - 1])
+                };
+            }
+        }
+        table[a.len()][b.len()]
+    }
+
+    /// Checks that the script walks both sequences in order & its `Equal` steps are an LCS
+    fn check_script(old: &str, new: &str) -> Vec<DiffOp> {
+        let (old, new): (Vec<char>, Vec<char>) = (old.chars().collect(), new.chars().collect());
+        let ops = myers_diff(&old, &new);
+        let (mut x, mut y) = (0, 0);
+        for op in &ops {
+            match *op {
+                DiffOp::Equal { old: o, new: n } => {
+                    assert_eq!((o, n), (x, y));
+                    assert_eq!(old[o], new[n]);
+                    x += 1;
+                    y += 1;
+                }
+                DiffOp::Delete { old: o } => {
+                    assert_eq!(o, x);
+                    x += 1;
+                }
+                DiffOp::Insert { new: n } => {
+                    assert_eq!(n, y);
+                    y += 1;
+                }
+            }
+        }
+        assert_eq!((x, y), (old.len(), new.len()));
+        let equal = ops.iter().filter(|op| matches!(op, DiffOp::Equal { .. })).count();
+        assert_eq!(equal, lcs_length(&old, &new));
+        ops
+    }
+
+    #[test]
+    fn equal_steps_form_a_longest_common_subsequence() {
+        for (old, new) in [("abcabba", "cbabac"), ("kitten", "sitting"), ("abc", "abc"), ("abc", "xyz"), ("ab", "ba")] {
+            check_script(old, new);
+        }
+    }
+
+    #[test]
+    fn every_short_pair() {
+        // All strings of up to 5 characters over a 3 letter alphabet
+        let mut strings = vec![String::new()];
+        for len in 1..=5 {
+            let mut longer = Vec::new();
+            for string in strings.iter().filter(|string| string.len() == len - 1) {
+                longer.extend(["a", "b", "c"].map(|c| format!("{}{}", string, c)));
+            }
+            strings.extend(longer);
+        }
+        for (i, old) in strings.iter().enumerate().step_by(7) {
+            for new in strings.iter().skip(i % 5).step_by(5) {
+                check_script(old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn hunks_group_consecutive_changes() {
+        let old: Vec<char> = "abcdefg".chars().collect();
+        let new: Vec<char> = "aXcdeYZg".chars().collect();
+        let ops = myers_diff(&old, &new);
+        let hunks = hunks(&ops);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].old_len, hunks[0].new_start, hunks[0].new_len), (1, 1, 1, 1));
+        assert_eq!(hunks[0].header(), "@@ -2,1 +2,1 @@");
+        assert_eq!((hunks[1].old_start, hunks[1].old_len, hunks[1].new_start, hunks[1].new_len), (5, 1, 5, 2));
+        assert_eq!(hunks[1].header(), "@@ -6,1 +6,2 @@");
+        assert!(!matches!(ops[hunks[1].op_index], DiffOp::Equal { .. }));
+
+        assert_eq!(DiffStats::new(&ops), DiffStats { lcs_length: 5, hunks: 2, changed_lines: 5 });
+    }
+
+    #[test]
+    fn hunks_of_pure_insertions_and_deletions() {
+        let ops = myers_diff(&["a", "b"], &["a", "x", "b", "y"]);
+        let headers: Vec<String> = hunks(&ops).iter().map(|hunk| hunk.header()).collect();
+        assert_eq!(headers, ["@@ -2,0 +2,1 @@", "@@ -3,0 +4,1 @@"]);
+
+        let ops = myers_diff(&["a", "b", "c"], &["c"]);
+        assert_eq!(hunks(&ops), [Hunk { op_index: 0, old_start: 0, old_len: 2, new_start: 0, new_len: 0 }]);
+    }
+
+    #[test]
+    fn stats_of_identical_and_empty_sequences() {
+        let identical = DiffStats::new(&myers_diff(&[1, 2, 3], &[1, 2, 3]));
+        assert_eq!(identical, DiffStats { lcs_length: 3, hunks: 0, changed_lines: 0 });
+        assert_eq!(DiffStats::new(&myers_diff::<u8>(&[], &[])), DiffStats::default());
+        assert_eq!(DiffStats::new(&myers_diff(&[], &[1, 2])), DiffStats { lcs_length: 0, hunks: 1, changed_lines: 2 });
+    }
+
+    #[test]
+    fn both_sides_empty() {
+        assert!(check_script("", "").is_empty());
+    }
+
+    #[test]
+    fn one_side_empty() {
+        assert_eq!(check_script("", "ab"), [DiffOp::Insert { new: 0 }, DiffOp::Insert { new: 1 }]);
+        assert_eq!(check_script("ab", ""), [DiffOp::Delete { old: 0 }, DiffOp::Delete { old: 1 }]);
+    }
+}
@@ -0,0 +1,84 @@
+use eframe::egui::{self, Color32, RichText};
+
+use crate::{classes::*, diff::*, histogram::*, histogram_view::*, parser::Dialect};
+
+const DELETED_COLOR: Color32 = Color32::from_rgb(220, 90, 90);
+const INSERTED_COLOR: Color32 = Color32::from_rgb(90, 180, 90);
+
+/// Shows the instructions of a key from both listings side by side
+///
+/// The rows are aligned with a diff of the two instruction sequences, instructions only in File 1 are red &
+/// instructions only in File 2 are green. Every hunk starts with its `@@` header. The instruction class & opcode
+/// histograms of both blocks are shown above the diff.
+pub fn show_block_diff(
+    ui: &mut egui::Ui,
+    key: &str,
+    block1: Option<&str>,
+    block2: Option<&str>,
+    source: Option<&str>,
+    dialect: Dialect,
+) {
+    ui.heading(key);
+    match source {
+        Some(source) => ui.monospace(source),
+        None => ui.weak("Source not found"),
+    };
+    ui.collapsing("Opcode histogram", |ui| {
+        let histogram1 = block1.map(block_histogram).unwrap_or_default();
+        let histogram2 = block2.map(block_histogram).unwrap_or_default();
+        show_histograms(
+            ui,
+            "block_class_histogram",
+            &class_label_histogram(&class_histogram(&histogram1, dialect)),
+            &class_label_histogram(&class_histogram(&histogram2, dialect)),
+        );
+        ui.separator();
+        show_histograms(ui, "block_histogram", &histogram1, &histogram2);
+    });
+    ui.separator();
+
+    let instructions1: Vec<&str> = block1.map(|block| block.lines().collect()).unwrap_or_default();
+    let instructions2: Vec<&str> = block2.map(|block| block.lines().collect()).unwrap_or_default();
+    let ops = myers_diff(&instructions1, &instructions2);
+    let hunks = hunks(&ops);
+    let stats = DiffStats::new(&ops);
+    ui.label(format!(
+        "{} changed line(s) in {} hunk(s), {} unchanged (LCS)",
+        stats.changed_lines, stats.hunks, stats.lcs_length
+    ));
+
+    egui::ScrollArea::vertical().id_source("block_diff").show(ui, |ui| {
+        egui::Grid::new("block_diff_table")
+            .striped(true)
+            .min_col_width(120.0)
+            .show(ui, |ui| {
+                ui.label(if block1.is_some() { "File 1" } else { "File 1 (missing)" });
+                ui.label(if block2.is_some() { "File 2" } else { "File 2 (missing)" });
+                ui.end_row();
+
+                let mut next_hunk = hunks.iter().peekable();
+                for (index, op) in ops.into_iter().enumerate() {
+                    if let Some(hunk) = next_hunk.next_if(|hunk| hunk.op_index == index) {
+                        ui.weak(RichText::new(hunk.header()).monospace());
+                        ui.label("");
+                        ui.end_row();
+                    }
+                    match op {
+                        DiffOp::Equal { old, new } => {
+                            ui.monospace(instructions1[old]);
+                            ui.monospace(instructions2[new]);
+                        }
+                        DiffOp::Delete { old } => {
+                            ui.label(RichText::new(instructions1[old]).monospace().color(DELETED_COLOR));
+                            ui.label("");
+                        }
+                        DiffOp::Insert { new } => {
+                            ui.label("");
+                            ui.label(RichText::new(instructions2[new]).monospace().color(INSERTED_COLOR));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{classes::*, levenstein::weighted_levenshtein, parser::Dialect};
+
+/// A cost for each instruction class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassCosts {
+    pub load_store: f64,
+    pub arithmetic: f64,
+    pub simd: f64,
+    pub branch: f64,
+    pub call: f64,
+    pub other: f64,
+}
+
+impl Default for ClassCosts {
+    /// Control flow weighs more than data movement, `other` is mostly padding such as `nop`
+    fn default() -> Self {
+        ClassCosts { load_store: 1.0, arithmetic: 1.0, simd: 1.0, branch: 1.5, call: 2.0, other: 0.5 }
+    }
+}
+
+impl ClassCosts {
+    pub fn get(&self, class: InstructionClass) -> f64 {
+        match class {
+            InstructionClass::LoadStore => self.load_store,
+            InstructionClass::Arithmetic => self.arithmetic,
+            InstructionClass::Simd => self.simd,
+            InstructionClass::Branch => self.branch,
+            InstructionClass::Call => self.call,
+            InstructionClass::Other => self.other,
+        }
+    }
+
+    pub fn get_mut(&mut self, class: InstructionClass) -> &mut f64 {
+        match class {
+            InstructionClass::LoadStore => &mut self.load_store,
+            InstructionClass::Arithmetic => &mut self.arithmetic,
+            InstructionClass::Simd => &mut self.simd,
+            InstructionClass::Branch => &mut self.branch,
+            InstructionClass::Call => &mut self.call,
+            InstructionClass::Other => &mut self.other,
+        }
+    }
+}
+
+/// Substitution cost of a specific pair, `from` & `to` are mnemonics or class names & the pair applies both ways
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairCost {
+    pub from: String,
+    pub to: String,
+    pub cost: f64,
+}
+
+/// Costs of the weighted edit distance
+///
+/// Inserting or deleting an instruction costs `indel` of its class. Replacing an instruction costs, in order:
+/// the first pair of `pairs` naming both mnemonics, the first pair matching both sides by mnemonic or class,
+/// `same_class` when both are in the same class, otherwise `cross_class` times the larger `indel` of the two.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditCosts {
+    pub indel: ClassCosts,
+    pub same_class: f64,
+    pub cross_class: f64,
+    pub pairs: Vec<PairCost>,
+}
+
+impl Default for EditCosts {
+    fn default() -> Self {
+        EditCosts { indel: ClassCosts::default(), same_class: 0.25, cross_class: 1.0, pairs: Vec::new() }
+    }
+}
+
+impl EditCosts {
+    /// First cost that is negative, which would let the distance go below zero
+    pub fn negative_cost(&self) -> Option<String> {
+        let classes = InstructionClass::ALL
+            .iter()
+            .map(|class| (format!("indel.{}", class.name()), self.indel.get(*class)));
+        let pairs = self.pairs.iter().map(|pair| (format!("pair {} / {}", pair.from, pair.to), pair.cost));
+        classes
+            .chain([("same_class".to_string(), self.same_class), ("cross_class".to_string(), self.cross_class)])
+            .chain(pairs)
+            .find(|(_, cost)| *cost < 0.0)
+            .map(|(name, _)| name)
+    }
+
+    /// Cost of replacing one mnemonic by a different one
+    fn substitution(&self, dialect: Dialect, mnemonic1: &str, mnemonic2: &str) -> f64 {
+        let (class1, class2) = (classify(dialect, mnemonic1), classify(dialect, mnemonic2));
+        // `exact` only accepts mnemonics, otherwise class names match as well
+        let matches = |name: &str, mnemonic: &str, class: InstructionClass, exact: bool| {
+            name.eq_ignore_ascii_case(mnemonic) || (!exact && name == class.name())
+        };
+        let applies = |pair: &PairCost, exact: bool| {
+            (matches(&pair.from, mnemonic1, class1, exact) && matches(&pair.to, mnemonic2, class2, exact))
+                || (matches(&pair.from, mnemonic2, class2, exact) && matches(&pair.to, mnemonic1, class1, exact))
+        };
+
+        let pair = self.pairs.iter().find(|pair| applies(pair, true));
+        if let Some(pair) = pair.or_else(|| self.pairs.iter().find(|pair| applies(pair, false))) {
+            return pair.cost;
+        }
+        if class1 == class2 {
+            self.same_class
+        } else {
+            self.cross_class * self.indel.get(class1).max(self.indel.get(class2))
+        }
+    }
+}
+
+/// Weighted edit distance between the instructions of two blocks
+///
+/// The costs of every distinct mnemonic & mnemonic pair are looked up once, the table is far smaller than the
+/// blocks.
+pub fn weighted_distance(block1: &str, block2: &str, costs: &EditCosts, dialect: Dialect) -> f64 {
+    let mut mnemonics: Vec<&str> = Vec::new();
+    let mut sequences: [Vec<usize>; 2] = Default::default();
+    for (sequence, block) in sequences.iter_mut().zip([block1, block2]) {
+        for mnemonic in block.lines().filter(|mnemonic| !mnemonic.is_empty()) {
+            let id = match mnemonics.iter().position(|known| *known == mnemonic) {
+                Some(id) => id,
+                None => {
+                    mnemonics.push(mnemonic);
+                    mnemonics.len() - 1
+                }
+            };
+            sequence.push(id);
+        }
+    }
+    let [sequence1, sequence2] = sequences;
+
+    let indel: Vec<f64> = mnemonics.iter().map(|mnemonic| costs.indel.get(classify(dialect, mnemonic))).collect();
+    let substitution: Vec<Vec<f64>> = mnemonics
+        .iter()
+        .map(|mnemonic1| mnemonics.iter().map(|mnemonic2| costs.substitution(dialect, mnemonic1, mnemonic2)).collect())
+        .collect();
+    weighted_levenshtein(&sequence1, &sequence2, |id| indel[*id], |id1, id2| substitution[*id1][*id2])
+}
+
+/// Cost of deleting every instruction of a block, the weighted distance to an empty block
+pub fn deletion_cost(block: &str, costs: &EditCosts, dialect: Dialect) -> f64 {
+    block.lines().filter(|mnemonic| !mnemonic.is_empty()).map(|mnemonic| costs.indel.get(classify(dialect, mnemonic))).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(from: &str, to: &str, cost: f64) -> PairCost {
+        PairCost { from: from.to_string(), to: to.to_string(), cost }
+    }
+
+    #[test]
+    fn class_rules_without_pairs() {
+        let costs = EditCosts::default();
+        // movl & movq are both load/store
+        assert_eq!(costs.substitution(Dialect::X86, "movl", "movq"), 0.25);
+        // cross_class times the larger indel, call costs 2 & load/store 1
+        assert_eq!(costs.substitution(Dialect::X86, "movl", "call"), 2.0);
+        assert_eq!(costs.substitution(Dialect::X86, "call", "movl"), 2.0);
+        let costs = EditCosts { cross_class: 0.5, ..EditCosts::default() };
+        assert_eq!(costs.substitution(Dialect::X86, "jmp", "addl"), 0.75);
+    }
+
+    #[test]
+    fn exact_pair_beats_class_pair() {
+        // The class pair comes first but the pair naming both mnemonics wins, whichever way round it is given
+        let costs = EditCosts {
+            pairs: vec![pair("load_store", "arithmetic", 0.5), pair("xorl", "movl", 0.1)],
+            ..EditCosts::default()
+        };
+        assert_eq!(costs.substitution(Dialect::X86, "movl", "xorl"), 0.1);
+        assert_eq!(costs.substitution(Dialect::X86, "xorl", "movl"), 0.1);
+        assert_eq!(costs.substitution(Dialect::X86, "movq", "addl"), 0.5);
+        assert_eq!(costs.substitution(Dialect::X86, "addl", "movq"), 0.5);
+    }
+
+    #[test]
+    fn mnemonic_and_class_pair() {
+        let costs = EditCosts { pairs: vec![pair("MOVL", "branch", 0.3)], ..EditCosts::default() };
+        assert_eq!(costs.substitution(Dialect::X86, "jne", "movl"), 0.3);
+        assert_eq!(costs.substitution(Dialect::X86, "jne", "movq"), 1.5);
+    }
+
+    #[test]
+    fn weighted_distance_of_blocks() {
+        let costs = EditCosts::default();
+        assert_eq!(weighted_distance("movl\nret", "movl\nret", &costs, Dialect::X86), 0.0);
+        assert_eq!(weighted_distance("movl\naddl", "movq\naddl", &costs, Dialect::X86), 0.25);
+        assert_eq!(weighted_distance("call", "", &costs, Dialect::X86), 2.0);
+        assert_eq!(deletion_cost("call\n\nmovl\naddl", &costs, Dialect::X86), 4.0);
+    }
+
+    #[test]
+    fn negative_costs_are_named() {
+        assert_eq!(EditCosts::default().negative_cost(), None);
+        let mut costs = EditCosts::default();
+        costs.indel.branch = -1.0;
+        assert_eq!(costs.negative_cost().as_deref(), Some("indel.branch"));
+        let costs = EditCosts { cross_class: -0.5, ..EditCosts::default() };
+        assert_eq!(costs.negative_cost().as_deref(), Some("cross_class"));
+        let costs = EditCosts { pairs: vec![pair("movl", "xorl", -0.1)], ..EditCosts::default() };
+        assert_eq!(costs.negative_cost().as_deref(), Some("pair movl / xorl"));
+    }
+}
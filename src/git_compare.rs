@@ -0,0 +1,103 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::compile::*;
+
+/// Listings & sources of one file generated at two git revisions
+#[derive(Debug)]
+pub struct RevisionListings {
+    /// Temp directory holding the listings & sources, they are removed when it is dropped
+    pub dir: TempDir,
+    pub listing_a: PathBuf,
+    pub listing_b: PathBuf,
+    /// Copy of the source file at each revision, used for the line remapping
+    pub source_a: PathBuf,
+    pub source_b: PathBuf,
+}
+
+impl RevisionListings {
+    /// Root of the revision A sources, the keys of both listings are relative to it
+    pub fn source_root(&self) -> PathBuf {
+        self.dir.path().join("source_a")
+    }
+}
+
+/// Runs a git command on the repository with the local git binary
+fn git(repo: &Path, args: &[&str]) -> Result<()> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo).args(args);
+    run_command(command)
+}
+
+/// Checks out one revision into a temporary worktree, compiles the file & keeps the listing & source
+///
+/// The compile command runs in the worktree root with the file path relative to the repository appended, so the
+/// `.file` directive & keys are the same for every revision. The worktree is removed even when compiling fails.
+fn build_revision(
+    repo: &Path,
+    revision: &str,
+    file: &Path,
+    compile_command: &str,
+    dir: &Path,
+    side: &str,
+) -> Result<(PathBuf, PathBuf)> {
+    let worktree = dir.join(format!("worktree_{}", side));
+    let worktree_str = worktree.to_string_lossy().to_string();
+    git(repo, &["worktree", "add", "--detach", &worktree_str, revision])?;
+
+    let listing = dir.join(format!("listing_{}.s", side));
+    let source = dir.join(format!("source_{}", side)).join(file);
+    let result = (|| -> Result<()> {
+        let argv = split_command_line(compile_command);
+        let (compiler, flags) = argv
+            .split_first()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty compile command"))?;
+
+        let mut command = Command::new(compiler);
+        command
+            .args(flags)
+            .args(["-S", "-fverbose-asm", "-o"])
+            .arg(&listing)
+            .arg(file)
+            .current_dir(&worktree);
+        run_command(command)?;
+
+        if let Some(parent) = source.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(worktree.join(file), &source)?;
+        Ok(())
+    })();
+
+    let removed = git(repo, &["worktree", "remove", "--force", &worktree_str]);
+    result.and(removed)?;
+    Ok((listing, source))
+}
+
+/// Generates the listing of a file at two revisions of a git repository
+///
+/// `file` is relative to the repository root & `compile_command` is the compiler with its flags, e.g.
+/// `gcc -O2 -Iinclude`. Pass the sources to `remap_listing` so that lines moved between the revisions still match.
+pub fn build_revisions(
+    repo: &Path,
+    revision_a: &str,
+    revision_b: &str,
+    file: &Path,
+    compile_command: &str,
+) -> Result<RevisionListings> {
+    let dir = create_temp_dir("git")?;
+    let (listing_a, source_a) = build_revision(repo, revision_a, file, compile_command, dir.path(), "a")?;
+    let (listing_b, source_b) = build_revision(repo, revision_b, file, compile_command, dir.path(), "b")?;
+
+    Ok(RevisionListings {
+        dir,
+        listing_a,
+        listing_b,
+        source_a,
+        source_b,
+    })
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use crate::parser::Listing;
+
+/// Number of times each mnemonic occurs
+pub type Histogram = BTreeMap<String, usize>;
+
+/// Opcode histogram of a block, the mnemonics are joined by `\n`
+pub fn block_histogram(block: &str) -> Histogram {
+    let mut histogram = Histogram::new();
+    for mnemonic in block.lines().filter(|mnemonic| !mnemonic.is_empty()) {
+        *histogram.entry(mnemonic.to_string()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Opcode histogram of every block of a listing
+pub fn listing_histogram(listing: &Listing) -> Histogram {
+    let mut histogram = Histogram::new();
+    for block in listing.blocks.values() {
+        for (mnemonic, count) in block_histogram(block) {
+            *histogram.entry(mnemonic).or_insert(0) += count;
+        }
+    }
+    histogram
+}
+
+/// Jensen-Shannon divergence of two opcode histograms in bits
+///
+/// 0 when both blocks use the opcodes in the same proportions & 1 when they have no opcode in common. Unlike the
+/// edit distance it ignores the order of the instructions, so a reordered block scores 0. Two empty histograms
+/// are identical & an empty one is fully different from a non-empty one.
+pub fn jensen_shannon_divergence(histogram1: &Histogram, histogram2: &Histogram) -> f64 {
+    let total1: usize = histogram1.values().sum();
+    let total2: usize = histogram2.values().sum();
+    match (total1, total2) {
+        (0, 0) => return 0.0,
+        (0, _) | (_, 0) => return 1.0,
+        _ => {}
+    }
+
+    // Only the opcodes present in a distribution contribute to its Kullback-Leibler term
+    let kl_term = |p: f64, m: f64| if p > 0.0 { p * (p / m).log2() } else { 0.0 };
+    let mut divergence = 0.0;
+    for mnemonic in histogram1.keys().chain(histogram2.keys().filter(|mnemonic| !histogram1.contains_key(*mnemonic))) {
+        let p = histogram1.get(mnemonic).copied().unwrap_or(0) as f64 / total1 as f64;
+        let q = histogram2.get(mnemonic).copied().unwrap_or(0) as f64 / total2 as f64;
+        let m = (p + q) / 2.0;
+        divergence += 0.5 * kl_term(p, m) + 0.5 * kl_term(q, m);
+    }
+    divergence.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disjoint_histograms_diverge_fully() {
+        let divergence = jensen_shannon_divergence(&block_histogram("movl\nmovl"), &block_histogram("xorl\naddl"));
+        assert!((divergence - 1.0).abs() < 1e-12, "{}", divergence);
+    }
+
+    #[test]
+    fn same_proportions_do_not_diverge() {
+        let divergence = jensen_shannon_divergence(&block_histogram("movl\naddl\nret"), &block_histogram("ret\naddl\nmovl"));
+        assert!(divergence.abs() < 1e-12, "{}", divergence);
+        let divergence = jensen_shannon_divergence(&block_histogram("movl\naddl"), &block_histogram("movl\naddl\nmovl\naddl"));
+        assert!(divergence.abs() < 1e-12, "{}", divergence);
+    }
+
+    #[test]
+    fn partial_overlap_is_symmetric() {
+        let (histogram1, histogram2) = (block_histogram("movl\naddl"), block_histogram("movl\nsubl"));
+        let divergence = jensen_shannon_divergence(&histogram1, &histogram2);
+        assert!((divergence - 0.5).abs() < 1e-12, "{}", divergence);
+        assert_eq!(divergence, jensen_shannon_divergence(&histogram2, &histogram1));
+    }
+
+    #[test]
+    fn empty_histograms() {
+        let empty = Histogram::new();
+        assert_eq!(jensen_shannon_divergence(&empty, &empty), 0.0);
+        assert_eq!(jensen_shannon_divergence(&empty, &block_histogram("ret")), 1.0);
+        assert_eq!(jensen_shannon_divergence(&block_histogram("ret"), &empty), 1.0);
+    }
+}
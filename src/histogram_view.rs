@@ -0,0 +1,45 @@
+use eframe::egui::{self, vec2, Color32, Sense};
+
+use crate::histogram::*;
+
+const FILE1_COLOR: Color32 = Color32::from_rgb(90, 140, 220);
+const FILE2_COLOR: Color32 = Color32::from_rgb(230, 150, 60);
+const BAR_WIDTH: f32 = 200.0;
+const BAR_HEIGHT: f32 = 7.0;
+
+/// Shows the opcode counts of both sides as paired horizontal bars, most used opcodes first
+///
+/// The bars of every opcode share one scale, the largest count of either side fills the full width.
+pub fn show_histograms(ui: &mut egui::Ui, id_source: &str, histogram1: &Histogram, histogram2: &Histogram) {
+    ui.horizontal(|ui| {
+        ui.colored_label(FILE1_COLOR, "■ File 1");
+        ui.colored_label(FILE2_COLOR, "■ File 2");
+    });
+
+    let count = |histogram: &Histogram, mnemonic: &str| histogram.get(mnemonic).copied().unwrap_or(0);
+    let mut mnemonics: Vec<&String> = histogram1.keys().chain(histogram2.keys()).collect();
+    mnemonics.sort();
+    mnemonics.dedup();
+    mnemonics.sort_by_key(|mnemonic| std::cmp::Reverse(count(histogram1, mnemonic) + count(histogram2, mnemonic)));
+    let max = histogram1.values().chain(histogram2.values()).copied().max().unwrap_or(0).max(1) as f32;
+
+    egui::Grid::new(id_source).striped(true).show(ui, |ui| {
+        for mnemonic in mnemonics {
+            let (count1, count2) = (count(histogram1, mnemonic), count(histogram2, mnemonic));
+            ui.monospace(mnemonic);
+
+            let (rect, _) = ui.allocate_exact_size(vec2(BAR_WIDTH, BAR_HEIGHT * 2.0 + 2.0), Sense::hover());
+            let painter = ui.painter();
+            let bar1 = egui::Rect::from_min_size(rect.min, vec2(BAR_WIDTH * count1 as f32 / max, BAR_HEIGHT));
+            let bar2 = egui::Rect::from_min_size(
+                rect.min + vec2(0.0, BAR_HEIGHT + 2.0),
+                vec2(BAR_WIDTH * count2 as f32 / max, BAR_HEIGHT),
+            );
+            painter.rect_filled(bar1, 0.0, FILE1_COLOR);
+            painter.rect_filled(bar2, 0.0, FILE2_COLOR);
+
+            ui.label(format!("{} / {}", count1, count2));
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,21 @@
+use std::{io::Result, path::Path};
+
+use crate::{parser::*, snapshot::*};
+
+/// Loads a listing to compare, parsed from assembly or read back from a snapshot file
+///
+/// Snapshots are loaded as the listing they were taken from, the options only apply to listings parsed from assembly.
+pub fn load_input(path: &Path, options: &ParserOptions) -> Result<Listing> {
+    if is_snapshot(path) {
+        return read_snapshot(path).map(|snapshot| snapshot.to_listing());
+    }
+    load_listing(path, options)
+}
+
+/// Checks that a file is a readable snapshot or looks like a supported listing, see `validate_listing`
+pub fn validate_input(path: &Path, options: &ParserOptions) -> Result<()> {
+    if is_snapshot(path) {
+        return read_snapshot(path).map(|_| ());
+    }
+    validate_listing(path, options)
+}
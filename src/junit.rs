@@ -0,0 +1,148 @@
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
+
+use crate::{batch::*, check::*, input::*, parser::*, report::*};
+
+/// One test case of the JUnit output, `failure` holds the message & the details shown by the CI
+struct TestCase {
+    class_name: String,
+    name: String,
+    failure: Option<(String, String)>,
+}
+
+/// Escapes text for XML element content & attribute values
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Violation messages & the instruction diff of every key they name
+fn failure_details(violations: &[&Violation], report: &Report) -> String {
+    let mut details = String::new();
+    for violation in violations {
+        match &violation.key {
+            Some(key) => {
+                let _ = writeln!(details, "{}: {}", key, violation.message);
+                for line in report.diff_lines(key) {
+                    let _ = writeln!(details, "    {}", line);
+                }
+            }
+            None => {
+                let _ = writeln!(details, "{}", violation.message);
+            }
+        }
+    }
+    details
+}
+
+/// Joins the messages of the violations into the one-line failure message
+fn failure_message(violations: &[&Violation]) -> String {
+    violations
+        .iter()
+        .map(|violation| match &violation.key {
+            Some(key) => format!("{}: {}", key, violation.message),
+            None => violation.message.clone(),
+        })
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+fn write_testsuites(suites: &[(String, Vec<TestCase>)]) -> String {
+    let tests: usize = suites.iter().map(|(_, cases)| cases.len()).sum();
+    let failures: usize = suites.iter().flat_map(|(_, cases)| cases).filter(|case| case.failure.is_some()).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(xml, "<testsuites name=\"dissimetrics\" tests=\"{}\" failures=\"{}\">", tests, failures);
+    for (name, cases) in suites {
+        let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+            escape_xml(name),
+            cases.len(),
+            failures
+        );
+        for case in cases {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape_xml(&case.class_name),
+                escape_xml(&case.name)
+            );
+            match &case.failure {
+                Some((message, details)) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                        escape_xml(message),
+                        escape_xml(details)
+                    );
+                }
+                None => xml.push_str("/>\n"),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Check results of a two-file comparison as JUnit XML, one test case per compared key
+///
+/// Thresholds on the whole file, the overall change & the unmatched key count, are reported by an extra
+/// `(overall)` test case.
+pub fn pair_junit(report: &Report, thresholds: &Thresholds) -> String {
+    let violations = check_comparison(report.comparison, thresholds);
+    let mut cases = Vec::new();
+
+    let overall: Vec<&Violation> = violations.iter().filter(|violation| violation.key.is_none()).collect();
+    cases.push(TestCase {
+        class_name: report.file_name.to_string(),
+        name: "(overall)".to_string(),
+        failure: (!overall.is_empty()).then(|| (failure_message(&overall), failure_details(&overall, report))),
+    });
+
+    for result in &report.comparison.results {
+        let key_violations: Vec<&Violation> =
+            violations.iter().filter(|violation| violation.key.as_deref() == Some(result.key.as_str())).collect();
+        cases.push(TestCase {
+            class_name: report.file_name.to_string(),
+            name: result.key.clone(),
+            failure: (!key_violations.is_empty())
+                .then(|| (failure_message(&key_violations), failure_details(&key_violations, report))),
+        });
+    }
+
+    write_testsuites(&[(report.file_name.to_string(), cases)])
+}
+
+/// Check results of a batch comparison as JUnit XML, one test case per file
+///
+/// The listings of failing files are read again for the instruction diffs of the offending keys.
+pub fn batch_junit(batch: &BatchResult, thresholds: &Thresholds, options: &ParserOptions) -> String {
+    let source_lines = HashMap::new();
+    let mut cases = Vec::new();
+
+    for entry in &batch.entries {
+        let violations = check_entry(entry, thresholds);
+        let failure = (!violations.is_empty()).then(|| {
+            let violations: Vec<&Violation> = violations.iter().collect();
+            let listing = |path: &Option<PathBuf>| path.as_deref().and_then(|path| load_input(path, options).ok()).unwrap_or_default();
+            let (listing1, listing2) = (listing(&entry.file1), listing(&entry.file2));
+            let comparison = entry.comparison.clone().unwrap_or_default();
+            let report = Report {
+                file_name: &entry.name,
+                comparison: &comparison,
+                listing1: &listing1,
+                listing2: &listing2,
+                source_lines: &source_lines,
+            };
+            (failure_message(&violations), failure_details(&violations, &report))
+        });
+        cases.push(TestCase { class_name: "batch".to_string(), name: entry.name.clone(), failure });
+    }
+
+    write_testsuites(&[("batch".to_string(), cases)])
+}
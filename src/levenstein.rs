@@ -1,4 +1,4 @@
-use std::{collections::HashMap, hash};
+use std::{collections::{HashMap, HashSet}, hash::{self, Hash}};
 
 use regex::bytes;
 
@@ -37,6 +37,178 @@ pub fn optimized_levenshtein(a: &[u8], b: &[u8]) -> usize {
     prev_row[len_b]
 }
 
+/// Levenshtein distance that gives up once the result must exceed `max`
+///
+/// Only the diagonal band of width `max` is computed & the function returns `None` as soon as every cell
+/// of a row is above `max`, so blocks that are far over the threshold cost O(max) per row instead of O(len).
+pub fn levenshtein_bounded(a: &[u8], b: &[u8], max: usize) -> Option<usize> {
+    let len_a = a.len();
+    let len_b = b.len();
+
+    // The length difference alone is a lower bound for the distance
+    if len_a.abs_diff(len_b) > max {
+        return None;
+    }
+    if len_a == 0 {
+        return Some(len_b);
+    }
+    if len_b == 0 {
+        return Some(len_a);
+    }
+
+    // Anything above `max` is stored as `over` so values outside the band never win a min()
+    let over = max + 1;
+    let mut prev_row = (0..=len_b).map(|j| j.min(over)).collect::<Vec<usize>>();
+    let mut curr_row = vec![over; len_b + 1];
+
+    for i in 1..=len_a {
+        let start = i.saturating_sub(max).max(1);
+        let end = (i + max).min(len_b);
+
+        curr_row[0] = i.min(over);
+        curr_row[start - 1] = if start == 1 { curr_row[0] } else { over };
+        let mut row_min = curr_row[start - 1];
+
+        for j in start..=end {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr_row[j] = (prev_row[j] + 1)          // deletion
+                .min(curr_row[j - 1] + 1)             // insertion
+                .min(prev_row[j - 1] + cost)          // substitution
+                .min(over);
+            row_min = row_min.min(curr_row[j]);
+        }
+        if end < len_b {
+            curr_row[end + 1] = over;
+        }
+
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+
+    let distance = prev_row[len_b];
+    if distance > max {
+        None
+    } else {
+        Some(distance)
+    }
+}
+
+/// Levenshtein distance with per-element edit costs
+///
+/// `indel` is the cost of inserting or deleting an element & `substitute` the cost of replacing one element by
+/// another, it is only called for elements that differ.
+pub fn weighted_levenshtein<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    indel: impl Fn(&T) -> f64,
+    substitute: impl Fn(&T, &T) -> f64,
+) -> f64 {
+    let mut prev_row = vec![0.0; b.len() + 1];
+    for j in 1..=b.len() {
+        prev_row[j] = prev_row[j - 1] + indel(&b[j - 1]);
+    }
+    let mut curr_row = vec![0.0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        let delete = indel(&a[i - 1]);
+        curr_row[0] = prev_row[0] + delete;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0.0 } else { substitute(&a[i - 1], &b[j - 1]) };
+            curr_row[j] = (prev_row[j] + delete)             // deletion
+                .min(curr_row[j - 1] + indel(&b[j - 1]))      // insertion
+                .min(prev_row[j - 1] + cost);                 // substitution
+        }
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+    prev_row[b.len()]
+}
+
+/// Distances of the same sequences with & without swaps of adjacent elements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OsaDistance {
+    pub osa: usize,
+    pub levenshtein: usize,
+}
+
+/// Optimal string alignment distance, Levenshtein plus swapping two adjacent elements as a single edit
+///
+/// A swapped pair can't be edited again, so `ab` → `bca` costs 3 here & 2 with `damerau_levenshtein`. The plain
+/// Levenshtein distance is filled in the same pass, the difference is what the swaps saved.
+pub fn osa_distance<T: PartialEq>(a: &[T], b: &[T]) -> OsaDistance {
+    let len_b = b.len();
+    let mut before_prev_row = vec![0; len_b + 1];
+    let mut prev_row = (0..=len_b).collect::<Vec<usize>>();
+    let mut curr_row = vec![0; len_b + 1];
+    let mut prev_levenshtein = prev_row.clone();
+    let mut curr_levenshtein = vec![0; len_b + 1];
+
+    for i in 1..=a.len() {
+        curr_row[0] = i;
+        curr_levenshtein[0] = i;
+        for j in 1..=len_b {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr_row[j] = (prev_row[j] + 1)             // deletion
+                .min(curr_row[j - 1] + 1)                // insertion
+                .min(prev_row[j - 1] + cost);            // substitution
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                curr_row[j] = curr_row[j].min(before_prev_row[j - 2] + 1); // transposition
+            }
+            curr_levenshtein[j] = (prev_levenshtein[j] + 1)
+                .min(curr_levenshtein[j - 1] + 1)
+                .min(prev_levenshtein[j - 1] + cost);
+        }
+        std::mem::swap(&mut before_prev_row, &mut prev_row);
+        std::mem::swap(&mut prev_row, &mut curr_row);
+        std::mem::swap(&mut prev_levenshtein, &mut curr_levenshtein);
+    }
+    OsaDistance { osa: prev_row[len_b], levenshtein: prev_levenshtein[len_b] }
+}
+
+/// Unrestricted Damerau-Levenshtein distance, elements may be edited again after being swapped
+///
+/// Lowrance-Wagner algorithm, a transposition across the edits in between goes back to the row before the last
+/// occurrence in `a` of the element of `b`. Instead of the whole (len_a + 2) × (len_b + 2) matrix only that row is
+/// kept for each distinct element of `b`, a handful of rows for instruction mnemonics.
+pub fn damerau_levenshtein<T: Eq + Hash>(a: &[T], b: &[T]) -> usize {
+    let len_b = b.len();
+    let max = a.len() + len_b;
+    let in_b: HashSet<&T> = b.iter().collect();
+    // Same layout as the rows of the full matrix, index 0 is the `max` border & index j + 1 is column j
+    let mut last_rows: HashMap<&T, (usize, Vec<usize>)> = HashMap::new();
+    let mut prev_row: Vec<usize> = [max].into_iter().chain(0..=len_b).collect();
+    let mut curr_row = vec![max; len_b + 2];
+
+    for i in 1..=a.len() {
+        curr_row[1] = i;
+        // Last column of this row where the elements matched
+        let mut last_match_col = 0;
+        for j in 1..=len_b {
+            let j1 = last_match_col;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_col = j;
+                0
+            } else {
+                1
+            };
+            curr_row[j + 1] = (prev_row[j] + cost)           // substitution
+                .min(curr_row[j] + 1)                        // insertion
+                .min(prev_row[j + 1] + 1);                   // deletion
+            if let Some((i1, row)) = last_rows.get(&b[j - 1]).filter(|_| j1 > 0) {
+                curr_row[j + 1] = curr_row[j + 1].min(row[j1] + (i - i1 - 1) + 1 + (j - j1 - 1)); // transposition
+            }
+        }
+        if in_b.contains(&a[i - 1]) {
+            let (row_index, row) = last_rows.entry(&a[i - 1]).or_insert_with(|| (0, vec![0; len_b + 2]));
+            *row_index = i;
+            row.copy_from_slice(&prev_row);
+        }
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+    prev_row[len_b + 1]
+}
+
 pub fn calculate_levinstine(str_v1: Vec<String>, str_v2:Vec<String>) -> u32{
     let mut distance = 0;
     for (s1, s2) in str_v1.iter().zip(str_v2.iter()){
@@ -88,4 +260,106 @@ pub fn generate_levinstine_map(sb1: HashMap<String, SuperBlock>, sb2: HashMap<St
     }
     let mut hash_map:HashMap<String, i32> = HashMap::new();
     hash_map
-}   
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_matches_full_distance_within_max() {
+        let pairs: [(&[u8], &[u8]); 5] =
+            [(b"kitten", b"sitting"), (b"flaw", b"lawn"), (b"abc", b"abc"), (b"", b"abc"), (b"abcdef", b"")];
+        for (a, b) in pairs {
+            let distance = optimized_levenshtein(a, b);
+            for max in distance..distance + 3 {
+                assert_eq!(levenshtein_bounded(a, b, max), Some(distance), "{:?} {:?} max {}", a, b, max);
+            }
+        }
+    }
+
+    #[test]
+    fn bounded_gives_up_above_max() {
+        assert_eq!(levenshtein_bounded(b"kitten", b"sitting", 2), None);
+        assert_eq!(levenshtein_bounded(b"abcdef", b"uvwxyz", 5), None);
+        // Same length but every element differs, only the band can reject it
+        assert_eq!(levenshtein_bounded(b"aaaa", b"bbbb", 3), None);
+    }
+
+    #[test]
+    fn bounded_with_zero_max() {
+        assert_eq!(levenshtein_bounded(b"abc", b"abc", 0), Some(0));
+        assert_eq!(levenshtein_bounded(b"abc", b"abd", 0), None);
+        assert_eq!(levenshtein_bounded(b"", b"", 0), Some(0));
+        assert_eq!(levenshtein_bounded(b"", b"a", 0), None);
+    }
+
+    #[test]
+    fn bounded_with_empty_inputs() {
+        assert_eq!(levenshtein_bounded(b"", b"abc", 3), Some(3));
+        assert_eq!(levenshtein_bounded(b"abc", b"", 2), None);
+        assert_eq!(levenshtein_bounded(b"", b"", 5), Some(0));
+    }
+
+    #[test]
+    fn weighted_with_unit_costs_is_levenshtein() {
+        for (a, b) in [("kitten", "sitting"), ("", "abc"), ("abc", ""), ("flaw", "lawn")] {
+            let distance = weighted_levenshtein(a.as_bytes(), b.as_bytes(), |_| 1.0, |_, _| 1.0);
+            assert_eq!(distance, optimized_levenshtein(a.as_bytes(), b.as_bytes()) as f64);
+        }
+    }
+
+    #[test]
+    fn weighted_prefers_cheaper_edits() {
+        // Replacing costs more than deleting & inserting, so `a` → `b` becomes two indels
+        assert_eq!(weighted_levenshtein(b"a", b"b", |_| 1.0, |_, _| 5.0), 2.0);
+        assert_eq!(weighted_levenshtein(b"a", b"b", |_| 1.0, |_, _| 0.5), 0.5);
+        // Per-element indel costs
+        let indel = |c: &u8| if *c == b'x' { 3.0 } else { 1.0 };
+        assert_eq!(weighted_levenshtein(b"axb", b"ab", indel, |_, _| 10.0), 3.0);
+        assert_eq!(weighted_levenshtein(b"", b"xa", indel, |_, _| 10.0), 4.0);
+    }
+
+    #[test]
+    fn osa_cannot_edit_a_swapped_pair() {
+        let osa = osa_distance(b"ab", b"bca");
+        assert_eq!(osa, OsaDistance { osa: 3, levenshtein: 3 });
+        assert_eq!(damerau_levenshtein(b"ab", b"bca"), 2);
+        assert_eq!(damerau_levenshtein(b"ca", b"abc"), 2);
+    }
+
+    #[test]
+    fn adjacent_swaps_cost_one_edit() {
+        assert_eq!(osa_distance(b"abcd", b"bacd"), OsaDistance { osa: 1, levenshtein: 2 });
+        assert_eq!(damerau_levenshtein(b"abcd", b"bacd"), 1);
+        assert_eq!(osa_distance(b"abcdef", b"badcfe"), OsaDistance { osa: 3, levenshtein: 4 });
+        assert_eq!(damerau_levenshtein(b"abcdef", b"badcfe"), 3);
+        // Without swaps both are the Levenshtein distance
+        assert_eq!(osa_distance(b"kitten", b"sitting"), OsaDistance { osa: 3, levenshtein: 3 });
+        assert_eq!(damerau_levenshtein(b"kitten", b"sitting"), 3);
+    }
+
+    #[test]
+    fn damerau_never_exceeds_osa() {
+        let words: [&[u8]; 8] = [b"", b"a", b"ab", b"ba", b"abc", b"cab", b"bca", b"acbd"];
+        for a in words {
+            for b in words {
+                let osa = osa_distance(a, b);
+                let damerau = damerau_levenshtein(a, b);
+                assert!(damerau <= osa.osa && osa.osa <= osa.levenshtein, "{:?} {:?}", a, b);
+                assert_eq!(osa.levenshtein, optimized_levenshtein(a, b));
+                assert_eq!(damerau, damerau_levenshtein(b, a));
+            }
+        }
+    }
+
+    #[test]
+    fn swap_distances_of_empty_inputs() {
+        assert_eq!(osa_distance::<u8>(b"", b""), OsaDistance { osa: 0, levenshtein: 0 });
+        assert_eq!(osa_distance(b"", b"abc"), OsaDistance { osa: 3, levenshtein: 3 });
+        assert_eq!(osa_distance(b"ab", b""), OsaDistance { osa: 2, levenshtein: 2 });
+        assert_eq!(damerau_levenshtein::<u8>(b"", b""), 0);
+        assert_eq!(damerau_levenshtein(b"", b"abc"), 3);
+        assert_eq!(damerau_levenshtein(b"ab", b""), 2);
+    }
+}
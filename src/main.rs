@@ -3,11 +3,50 @@
 mod parser;
 mod levenstein;
 mod cosine_similarties;
-use std::{collections::HashMap, path::PathBuf};
+mod compare;
+mod cli;
+mod diff;
+mod source_map;
+mod source_view;
+mod diff_view;
+mod batch;
+mod matrix;
+mod compile;
+mod git_compare;
+mod check;
+mod snapshot;
+mod input;
+mod report;
+mod junit;
+mod config;
+mod session;
+mod watch;
+mod histogram;
+mod histogram_view;
+mod classes;
+mod edit_costs;
+mod similarity;
+use std::collections::{HashMap, HashSet};
 
 use parser::*;
-use levenstein::*;
-use cosine_similarties::*;
+use compare::*;
+use source_map::*;
+use source_view::*;
+use batch::*;
+use matrix::*;
+use compile::*;
+use git_compare::*;
+use snapshot::*;
+use input::*;
+use report::*;
+use config::*;
+use check::*;
+use session::*;
+use watch::*;
+use histogram::*;
+use histogram_view::*;
+use classes::*;
+use similarity::*;
 
 use eframe::egui;
 use rfd::FileDialog;
@@ -16,16 +55,50 @@ pub struct MyApp {
     file2_path: Option<std::path::PathBuf>,
     file1_name: Option<String>,
     file2_name: Option<String>,
-    levenshtein_distance: i32,
-    cosine_similarity: f64,
-    change_percentage: f64,
+    source1_path: Option<std::path::PathBuf>, // Old & new version of the source, used to re-key File 2
+    source2_path: Option<std::path::PathBuf>,
+    source_root: Option<std::path::PathBuf>, // Searched before the directory of File 1 for the source files
     error_message: Option<String>,
-    should_display_output: bool,
-    file1_text: String,
-    file2_text: String,
-    file_length: f64,
-    key_distance: i32,
-    results: Vec<(String, i32, i32, f64, f64)>, // Add this field to store the results
+    use_max_distance: bool,
+    max_distance: usize,
+    threads: usize, // 0 uses every core
+    comparison: Option<Comparison>, // Results of the last calculation
+    compared_pair: Option<(std::path::PathBuf, std::path::PathBuf)>, // Files behind comparison
+    listing1: Listing,
+    listing2: Listing,
+    source_lines: HashMap<String, String>, // Source text of every key that could be found
+    selected_key: Option<String>, // Key shown in the diff view
+    batch_dir1: Option<std::path::PathBuf>,
+    batch_dir2: Option<std::path::PathBuf>,
+    batch: Option<BatchResult>, // Roll-up of the last directory comparison
+    matrix_paths: Vec<std::path::PathBuf>,
+    matrix: Option<ComparisonMatrix>, // Pairwise metrics of the listings in matrix_paths
+    matrix_metric: MatrixMetric,
+    compile_source: Option<std::path::PathBuf>, // Source compiled twice by "Compile and Compare"
+    compile_flags_a: String,
+    compile_flags_b: String,
+    compiler_a: String,
+    compiler_b: String,
+    compilers: Vec<String>, // C/C++ compilers found on PATH
+    compile_commands: Option<std::path::PathBuf>, // compile_commands.json re-run with both flag sets
+    git_repo: Option<std::path::PathBuf>,
+    git_revision_a: String,
+    git_revision_b: String,
+    git_file: String, // Relative to the repository root
+    git_command: String, // Compiler & flags run in the worktree root
+    temp_dir: Option<TempDir>, // Listings generated by the last compile, removed when replaced
+    compiled_root: Option<std::path::PathBuf>, // Directory of the compiled source, the root while its listings are selected
+    revisions: Option<RevisionListings>, // Last git comparison, its sources are used while its listings are selected
+    markdown_top: usize, // Most-changed keys listed in the Markdown report
+    config: Config, // Parser, metrics & thresholds of the project, edited in the settings panel
+    config_path: Option<std::path::PathBuf>,
+    ignored_directives_text: String, // Comma separated lists edited in the settings panel
+    ignored_mnemonics_text: String,
+    recent: Vec<RecentComparison>, // Most recent first, listed in the "Recent comparisons" menu
+    sort_column: SortColumn, // Order of the per-key table
+    sort_ascending: bool,
+    watcher: Option<FileWatcher>, // Set while "Watch files" is on
+    changed_keys: HashSet<String>, // Keys whose metrics changed since the previous run of the same pair
 }
 
 impl Default for MyApp {
@@ -35,35 +108,817 @@ impl Default for MyApp {
             file2_path: None,
             file1_name: None,
             file2_name: None,
-            levenshtein_distance: 0,
-            cosine_similarity: 0.0,
-            change_percentage:0.0,
+            source1_path: None,
+            source2_path: None,
+            source_root: None,
             error_message: None,
-            should_display_output: false,
-            file1_text: String::new(),
-            file2_text: String::new(),
-            file_length: 0.0,
-            key_distance: 0,
-            results: Vec::new(), // Initialize the results field
+            use_max_distance: false,
+            max_distance: 10,
+            threads: 0,
+            comparison: None,
+            compared_pair: None,
+            listing1: Listing::default(),
+            listing2: Listing::default(),
+            source_lines: HashMap::new(),
+            selected_key: None,
+            batch_dir1: None,
+            batch_dir2: None,
+            batch: None,
+            matrix_paths: Vec::new(),
+            matrix: None,
+            matrix_metric: MatrixMetric::Distance,
+            compile_source: None,
+            compile_flags_a: "-O1".to_string(),
+            compile_flags_b: "-O2".to_string(),
+            compiler_a: DEFAULT_COMPILER.to_string(),
+            compiler_b: DEFAULT_COMPILER.to_string(),
+            compilers: find_compilers(),
+            compile_commands: None,
+            git_repo: None,
+            git_revision_a: "HEAD~1".to_string(),
+            git_revision_b: "HEAD".to_string(),
+            git_file: String::new(),
+            git_command: format!("{} -O2", DEFAULT_COMPILER),
+            temp_dir: None,
+            compiled_root: None,
+            revisions: None,
+            markdown_top: DEFAULT_TOP_KEYS,
+            config: Config::default(),
+            config_path: None,
+            ignored_directives_text: String::new(),
+            ignored_mnemonics_text: String::new(),
+            recent: Vec::new(),
+            sort_column: SortColumn::Key,
+            sort_ascending: true,
+            watcher: None,
+            changed_keys: HashSet::new(),
+        }
+    }
+}
+
+/// Background of the keys whose metrics changed since the previous run
+const CHANGED_KEY_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(110, 90, 20, 120);
+
+/// Splits a comma separated list from the settings panel
+fn split_list(text: &str) -> Vec<String> {
+    text.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+/// Checkbox & value for an optional threshold, unchecked is None
+fn optional_value<T: egui::emath::Numeric>(ui: &mut egui::Ui, label: &str, value: &mut Option<T>, default: T) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, label).changed() {
+            *value = enabled.then_some(default);
+        }
+        if let Some(value) = value {
+            ui.add(egui::DragValue::new(value));
+        }
+    });
+}
+
+/// Clickable column header of the per-key table, a second click reverses the order
+fn sort_header(ui: &mut egui::Ui, label: &str, column: SortColumn, sort_column: &mut SortColumn, ascending: &mut bool) {
+    let text = match (*sort_column == column, *ascending) {
+        (true, true) => format!("{} ⏶", label),
+        (true, false) => format!("{} ⏷", label),
+        (false, _) => label.to_string(),
+    };
+    if ui.selectable_label(*sort_column == column, text).clicked() {
+        if *sort_column == column {
+            *ascending = !*ascending;
+        } else {
+            *sort_column = column;
+            *ascending = true;
         }
     }
 }
 
 impl MyApp {
+    /// Restores the previous session, then takes the project configuration found from the working directory
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = MyApp::default();
+        if let Some(state) = cc.storage.and_then(|storage| eframe::get_value::<SessionState>(storage, eframe::APP_KEY)) {
+            app.restore_session(state);
+        }
+        if let Some(path) = std::env::current_dir().ok().and_then(|dir| find_config(&dir)) {
+            app.load_config_file(path);
+        }
+        app
+    }
+
     // Add a reset method that resets all values to their default state
     pub fn reset(&mut self) {
+        // The recent comparisons & the configuration outlive a reset
+        let recent = std::mem::take(&mut self.recent);
+        let config_path = self.config_path.take();
         *self = MyApp::default(); // Set all fields to default values
+        self.recent = recent;
+        if let Some(path) = config_path {
+            self.load_config_file(path);
+        }
+    }
+
+    fn session_state(&self) -> SessionState {
+        SessionState {
+            recent: self.recent.clone(),
+            file1_path: self.file1_path.clone(),
+            file2_path: self.file2_path.clone(),
+            source1_path: self.source1_path.clone(),
+            source2_path: self.source2_path.clone(),
+            source_root: self.source_root.clone(),
+            sort_column: self.sort_column,
+            sort_ascending: self.sort_ascending,
+            use_max_distance: self.use_max_distance,
+            max_distance: self.max_distance,
+            threads: self.threads,
+            matrix_metric: Some(self.matrix_metric),
+            compile_flags_a: self.compile_flags_a.clone(),
+            compile_flags_b: self.compile_flags_b.clone(),
+            compiler_a: self.compiler_a.clone(),
+            compiler_b: self.compiler_b.clone(),
+            git_revision_a: self.git_revision_a.clone(),
+            git_revision_b: self.git_revision_b.clone(),
+            git_command: self.git_command.clone(),
+            markdown_top: self.markdown_top,
+            config: self.config.clone(),
+            config_path: self.config_path.clone(),
+        }
+    }
+
+    fn restore_session(&mut self, state: SessionState) {
+        self.recent = state.recent;
+        self.file1_path = state.file1_path;
+        self.file2_path = state.file2_path;
+        self.source1_path = state.source1_path;
+        self.source2_path = state.source2_path;
+        self.source_root = state.source_root;
+        self.sort_column = state.sort_column;
+        self.sort_ascending = state.sort_ascending;
+        self.use_max_distance = state.use_max_distance;
+        self.max_distance = state.max_distance;
+        self.threads = state.threads;
+        self.matrix_metric = state.matrix_metric.unwrap_or(self.matrix_metric);
+        self.compile_flags_a = state.compile_flags_a;
+        self.compile_flags_b = state.compile_flags_b;
+        self.compiler_a = state.compiler_a;
+        self.compiler_b = state.compiler_b;
+        self.git_revision_a = state.git_revision_a;
+        self.git_revision_b = state.git_revision_b;
+        self.git_command = state.git_command;
+        self.markdown_top = state.markdown_top.max(1);
+        self.ignored_directives_text = state.config.parser.ignored_directives.join(", ");
+        self.ignored_mnemonics_text = state.config.parser.ignored_mnemonics.join(", ");
+        self.config = state.config;
+        self.config_path = state.config_path;
+    }
+
+    /// Takes files or directories dropped onto the window & starts the comparison once both sides are filled
+    ///
+    /// One file fills File 1, then File 2, then starts a new pair. Two files fill both sides at once. Directories
+    /// fill the batch comparison the same way. Files that do not look like listings are rejected.
+    fn drop_paths(&mut self, paths: Vec<std::path::PathBuf>) {
+        self.error_message = None;
+        let (dirs, files): (Vec<_>, Vec<_>) = paths.into_iter().partition(|path| path.is_dir());
+
+        match (dirs.as_slice(), files.as_slice()) {
+            ([], [file]) => {
+                if let Err(error) = validate_input(file, &self.config.parser) {
+                    self.error_message = Some(format!("Not a supported listing: {}", error));
+                    return;
+                }
+                match (&self.file1_path, &self.file2_path) {
+                    (Some(_), None) => self.file2_path = Some(file.clone()),
+                    _ => {
+                        self.file1_path = Some(file.clone());
+                        self.file2_path = None;
+                    }
+                }
+            }
+            ([], [file1, file2]) => {
+                for file in [file1, file2] {
+                    if let Err(error) = validate_input(file, &self.config.parser) {
+                        self.error_message = Some(format!("Not a supported listing: {}", error));
+                        return;
+                    }
+                }
+                self.file1_path = Some(file1.clone());
+                self.file2_path = Some(file2.clone());
+            }
+            ([dir], []) => {
+                match (&self.batch_dir1, &self.batch_dir2) {
+                    (Some(_), None) => self.batch_dir2 = Some(dir.clone()),
+                    _ => {
+                        self.batch_dir1 = Some(dir.clone());
+                        self.batch_dir2 = None;
+                    }
+                }
+                if self.batch_dir2.is_some() {
+                    self.calculate_batch();
+                }
+                return;
+            }
+            ([dir1, dir2], []) => {
+                self.batch_dir1 = Some(dir1.clone());
+                self.batch_dir2 = Some(dir2.clone());
+                self.calculate_batch();
+                return;
+            }
+            _ => {
+                self.error_message = Some("Drop one or two listings, or one or two directories.".to_string());
+                return;
+            }
+        }
+
+        if self.file1_path.is_some() && self.file2_path.is_some() {
+            self.calculate();
+        }
+    }
+
+    /// Both listings & the remapping sources, the files that "Watch files" checks for changes
+    fn watched_paths(&self) -> Vec<std::path::PathBuf> {
+        [&self.file1_path, &self.file2_path, &self.source1_path, &self.source2_path]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Compares again when a watched file changed, following the selection when other files are picked
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        let paths = self.watched_paths();
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        if watcher.paths() != paths.as_slice() {
+            *watcher = FileWatcher::new(paths);
+        } else if watcher.poll() {
+            self.calculate();
+        }
+        ctx.request_repaint_after(POLL_INTERVAL);
+    }
+
+    /// Selects a previous file pair & compares it again
+    fn open_recent(&mut self, recent: RecentComparison) {
+        self.file1_path = Some(recent.file1);
+        self.file2_path = Some(recent.file2);
+        self.source1_path = recent.source1;
+        self.source2_path = recent.source2;
+        self.source_root = recent.source_root;
+        self.calculate();
+    }
+
+    /// Takes the inputs & options of a configuration file, keeping what it does not set
+    fn load_config_file(&mut self, path: std::path::PathBuf) {
+        let config = match load_config(&path) {
+            Ok(config) => config,
+            Err(error) => {
+                self.error_message = Some(format!("Failed to read the configuration: {}", error));
+                return;
+            }
+        };
+
+        let inputs = &config.inputs;
+        self.file1_path = inputs.file1.clone().or(self.file1_path.take());
+        self.file2_path = inputs.file2.clone().or(self.file2_path.take());
+        self.source1_path = inputs.source1.clone().or(self.source1_path.take());
+        self.source2_path = inputs.source2.clone().or(self.source2_path.take());
+        self.source_root = inputs.source_root.clone().or(self.source_root.take());
+        if let Some(max_distance) = config.metrics.max_distance {
+            self.max_distance = max_distance;
+        }
+        self.use_max_distance = config.metrics.max_distance.is_some();
+        self.threads = config.metrics.threads.unwrap_or(0);
+        self.ignored_directives_text = config.parser.ignored_directives.join(", ");
+        self.ignored_mnemonics_text = config.parser.ignored_mnemonics.join(", ");
+        self.config = config;
+        self.config_path = Some(path);
+    }
+
+    /// Writes the settings & the current inputs to the configuration file, asking for a path the first time
+    fn save_config_file(&mut self) {
+        let path = match &self.config_path {
+            Some(path) => path.clone(),
+            None => match FileDialog::new().add_filter("TOML", &["toml"]).set_file_name(CONFIG_FILE_NAME).save_file() {
+                Some(path) => path,
+                None => return,
+            },
+        };
+
+        self.config.inputs = InputConfig {
+            file1: self.file1_path.clone(),
+            file2: self.file2_path.clone(),
+            source1: self.source1_path.clone(),
+            source2: self.source2_path.clone(),
+            source_root: self.source_root.clone(),
+        };
+        self.config.metrics.max_distance = self.use_max_distance.then_some(self.max_distance);
+        self.config.metrics.threads = (self.threads > 0).then_some(self.threads);
+        match save_config(&self.config, &path) {
+            Ok(()) => self.config_path = Some(path),
+            Err(error) => self.error_message = Some(format!("Failed to save the configuration: {}", error)),
+        }
+    }
+
+    /// Parser, metric & threshold settings of the configuration file
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Load Config").clicked() {
+                if let Some(path) = FileDialog::new().add_filter("TOML", &["toml"]).pick_file() {
+                    self.load_config_file(path);
+                }
+            }
+            if ui.button("Save Config").clicked() {
+                self.save_config_file();
+            }
+            match &self.config_path {
+                Some(path) => ui.label(format!("Config: {}", path.display())),
+                None => ui.weak(format!("No {} found", CONFIG_FILE_NAME)),
+            };
+        });
+
+        ui.label("Parser (applied on the next calculation)");
+        let parser = &mut self.config.parser;
+        egui::ComboBox::from_label("Dialect")
+            .selected_text(parser.dialect.name())
+            .show_ui(ui, |ui| {
+                for dialect in Dialect::ALL {
+                    ui.selectable_value(&mut parser.dialect, dialect, dialect.name());
+                }
+            });
+        ui.checkbox(&mut parser.keep_directives, "Keep directives");
+        egui::Grid::new("settings_lists").show(ui, |ui| {
+            ui.label("Ignored directives");
+            if ui.text_edit_singleline(&mut self.ignored_directives_text).changed() {
+                parser.ignored_directives = split_list(&self.ignored_directives_text);
+            }
+            ui.end_row();
+            ui.label("Ignored mnemonics");
+            if ui.text_edit_singleline(&mut self.ignored_mnemonics_text).changed() {
+                parser.ignored_mnemonics = split_list(&self.ignored_mnemonics_text);
+            }
+            ui.end_row();
+        });
+        ui.checkbox(&mut parser.normalize.lowercase, "Compare mnemonics case-insensitively");
+        ui.checkbox(&mut parser.normalize.strip_prefixes, "Strip instruction prefixes (lock, rep, ...)");
+
+        ui.horizontal(|ui| {
+            ui.label("Metrics");
+            for metric in METRICS {
+                let mut enabled = self.config.metrics.is_enabled(metric);
+                if ui.checkbox(&mut enabled, metric).changed() {
+                    self.config.metrics.set_enabled(metric, enabled);
+                }
+            }
+        });
+        ui.checkbox(&mut self.config.metrics.normalized, "Also show each metric as a 0–1 similarity");
+
+        ui.label("Weighted distance costs (pairs are set in the configuration file)");
+        let edit_costs = &mut self.config.edit_costs;
+        egui::Grid::new("settings_edit_costs").show(ui, |ui| {
+            for class in InstructionClass::ALL {
+                ui.label(format!("Insert/delete {}", class.label()));
+                ui.add(egui::DragValue::new(edit_costs.indel.get_mut(class)).speed(0.05).clamp_range(0.0..=f64::MAX));
+                ui.end_row();
+            }
+            ui.label("Replace within a class");
+            ui.add(egui::DragValue::new(&mut edit_costs.same_class).speed(0.05).clamp_range(0.0..=f64::MAX));
+            ui.end_row();
+            ui.label("Replace across classes (× larger insert/delete cost)");
+            ui.add(egui::DragValue::new(&mut edit_costs.cross_class).speed(0.05).clamp_range(0.0..=f64::MAX));
+            ui.end_row();
+        });
+
+        ui.label("Thresholds");
+        let thresholds = &mut self.config.thresholds;
+        optional_value(ui, "Max overall change %", &mut thresholds.max_change, 5.0);
+        optional_value(ui, "Max change % of a key", &mut thresholds.max_key_change, 50.0);
+        optional_value(ui, "Max unmatched keys", &mut thresholds.max_unmatched, 0);
+        for metric in SimilarityMetric::ALL {
+            let label = format!("Min {} of a key", metric.label());
+            let limit = thresholds.min_key_similarity.get_mut(metric);
+            optional_value(ui, &label, limit, 0.5);
+            if let Some(limit) = limit {
+                *limit = limit.clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    fn compare_options(&self) -> CompareOptions {
+        CompareOptions {
+            max_distance: self.use_max_distance.then_some(self.max_distance),
+            threads: (self.threads > 0).then_some(self.threads),
+            parser: self.config.parser.clone(),
+            edit_costs: self.config.edit_costs.clone(),
+            skipped_metrics: skipped_metrics(&self.config.metrics, &self.config.thresholds),
+        }
+    }
+
+    /// Compares every listing pair of the two selected directories
+    fn calculate_batch(&mut self) {
+        self.error_message = None;
+
+        if let (Some(dir1), Some(dir2)) = (&self.batch_dir1, &self.batch_dir2) {
+            match compare_directories(dir1, dir2, &self.compare_options()) {
+                Ok(batch) => self.batch = Some(batch),
+                Err(error) => self.error_message = Some(format!("Failed to compare the directories: {}", error)),
+            }
+        } else {
+            self.error_message = Some("Please select both directories.".to_string());
+        }
+    }
+
+    /// Roll-up table of the batch comparison, clicking a file opens its per-key results
+    fn show_batch(&mut self, ui: &mut egui::Ui) {
+        let Some(batch) = &self.batch else {
+            return;
+        };
+        let total_prefix = if batch.over_threshold > 0 { ">= " } else { "" };
+        let mut opened = None;
+
+        egui::ScrollArea::vertical().id_source("batch_scroll").max_height(300.0).show(ui, |ui| {
+            egui::Grid::new("batch_table")
+                .striped(true)
+                .min_col_width(90.0)
+                .show(ui, |ui| {
+                    ui.label("File");
+                    ui.label("Matched Keys");
+                    ui.label("Unmatched Keys");
+                    ui.label("Levenshtein Distance");
+                    ui.label("Change %");
+                    ui.label("Overall Distance");
+                    ui.label("Overall Change %");
+                    ui.end_row();
+
+                    for (index, entry) in batch.entries.iter().enumerate() {
+                        match (&entry.comparison, &entry.error) {
+                            (Some(comparison), _) => {
+                                if ui.selectable_label(false, &entry.name).clicked() {
+                                    opened = Some(index);
+                                }
+                                ui.label(format!("{}", comparison.matched_keys));
+                                ui.label(format!("{}", comparison.unmatched_keys));
+                                ui.label(format!("{}", comparison.levenshtein_distance));
+                                ui.label(format!("{:.2}%", comparison.change_percentage));
+                                ui.label(format!("{}", comparison.overall_distance));
+                                ui.label(format!("{:.2}%", comparison.overall_change_percentage));
+                            }
+                            (None, error) => {
+                                ui.weak(&entry.name);
+                                ui.weak(error.as_deref().unwrap_or("N/A"));
+                            }
+                        }
+                        ui.end_row();
+                    }
+
+                    ui.strong("Project total");
+                    ui.strong(format!("{}", batch.matched_keys));
+                    ui.strong(format!("{}", batch.unmatched_keys));
+                    ui.strong(format!("{}{}", total_prefix, batch.levenshtein_distance));
+                    ui.strong(format!("{}{:.2}%", total_prefix, batch.change_percentage));
+                    ui.strong(format!("{}{}", total_prefix, batch.overall_distance));
+                    ui.strong(format!("{}{:.2}%", total_prefix, batch.overall_change_percentage));
+                    ui.end_row();
+                });
+        });
+
+        if let Some(index) = opened {
+            let entry = &batch.entries[index];
+            self.file1_path = entry.file1.clone();
+            self.file2_path = entry.file2.clone();
+            self.calculate();
+        }
+    }
+
+    /// Heatmap of the comparison matrix, clicking a cell opens the per-key results of that pair
+    fn show_matrix(&mut self, ui: &mut egui::Ui) {
+        let Some(matrix) = &self.matrix else {
+            return;
+        };
+        let mut opened = None;
+
+        ui.horizontal(|ui| {
+            for metric in [MatrixMetric::Distance, MatrixMetric::CosineSimilarity, MatrixMetric::ChangePercentage] {
+                ui.radio_value(&mut self.matrix_metric, metric, metric.name());
+            }
+        });
+
+        egui::ScrollArea::both().id_source("matrix_scroll").max_height(400.0).show(ui, |ui| {
+            egui::Grid::new("matrix_table").min_col_width(80.0).show(ui, |ui| {
+                ui.label("");
+                for label in &matrix.labels {
+                    ui.strong(label);
+                }
+                ui.end_row();
+
+                for (i, row) in matrix.cells.iter().enumerate() {
+                    ui.strong(&matrix.labels[i]);
+                    for (j, cell) in row.iter().enumerate() {
+                        // Green for identical pairs through red for the most different pair
+                        let heat = self.matrix_metric.heat(cell, matrix);
+                        let color = egui::Color32::from_rgb((80.0 + 175.0 * heat) as u8, (200.0 - 140.0 * heat) as u8, 80);
+                        let text = egui::RichText::new(self.matrix_metric.label(cell)).color(egui::Color32::BLACK);
+                        if ui.add(egui::Button::new(text).fill(color)).clicked() && i != j {
+                            opened = Some((i, j));
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some((i, j)) = opened {
+            self.file1_path = Some(matrix.paths[i].clone());
+            self.file2_path = Some(matrix.paths[j].clone());
+            self.calculate();
+        }
+    }
+
+    /// Compiles the selected source with both flag sets & compares the generated listings
+    fn compile_and_compare(&mut self) {
+        let Some(source) = self.compile_source.clone() else {
+            self.error_message = Some("Please select a source file.".to_string());
+            return;
+        };
+        let side_a = CompileSide {
+            compiler: self.compiler_a.clone(),
+            flags: split_flags(&self.compile_flags_a),
+        };
+        let side_b = CompileSide {
+            compiler: self.compiler_b.clone(),
+            flags: split_flags(&self.compile_flags_b),
+        };
+
+        match compile_pair(&source, &side_a, &side_b) {
+            Ok((dir, listing_a, listing_b)) => {
+                self.temp_dir = Some(dir);
+                self.file1_path = Some(listing_a);
+                self.file2_path = Some(listing_b);
+                // The listings live in a temp directory, the sources are next to the compiled file
+                self.compiled_root = source.parent().map(|dir| dir.to_path_buf());
+                self.calculate();
+            }
+            Err(error) => self.error_message = Some(format!("Compilation failed: {}", error)),
+        }
+    }
+
+    /// Re-compiles the whole compilation database with both flag sets & compares the two trees in batch mode
+    fn compile_project_and_compare(&mut self) {
+        let Some(compile_commands) = self.compile_commands.clone() else {
+            self.error_message = Some("Please select a compile_commands.json.".to_string());
+            return;
+        };
+        let flags_a = split_flags(&self.compile_flags_a);
+        let flags_b = split_flags(&self.compile_flags_b);
+        match compile_project(&compile_commands, &flags_a, &flags_b) {
+            Ok(project) => {
+                self.temp_dir = Some(project.dir);
+                self.compiled_root = None;
+                self.batch_dir1 = Some(project.dir_a);
+                self.batch_dir2 = Some(project.dir_b);
+                self.calculate_batch();
+                if !project.errors.is_empty() && self.error_message.is_none() {
+                    self.error_message = Some(format!(
+                        "{} compilation(s) failed:\n{}",
+                        project.errors.len(),
+                        project.errors.join("\n")
+                    ));
+                }
+            }
+            Err(error) => self.error_message = Some(format!("Failed to compile the project: {}", error)),
+        }
+    }
+
+    /// Builds the file at both git revisions & compares the listings, re-keyed through the two source versions
+    fn compare_revisions(&mut self) {
+        let Some(repo) = self.git_repo.clone() else {
+            self.error_message = Some("Please select a git repository.".to_string());
+            return;
+        };
+        if self.git_file.trim().is_empty() {
+            self.error_message = Some("Please enter the source file to compile.".to_string());
+            return;
+        }
+
+        let file = std::path::PathBuf::from(self.git_file.trim());
+        match build_revisions(&repo, &self.git_revision_a, &self.git_revision_b, &file, &self.git_command) {
+            Ok(revisions) => {
+                // The selected sources are left as they are, the revision copies only apply to these listings
+                self.file1_path = Some(revisions.listing_a.clone());
+                self.file2_path = Some(revisions.listing_b.clone());
+                self.revisions = Some(revisions);
+                self.calculate();
+            }
+            Err(error) => self.error_message = Some(format!("Failed to build the revisions: {}", error)),
+        }
+    }
+
+    /// Saves the parsed listing as a snapshot file chosen by the user
+    fn save_snapshot(&mut self, listing_path: &std::path::Path) {
+        let file_name = listing_path
+            .file_stem()
+            .map(|stem| format!("{}.{}", stem.to_string_lossy(), SNAPSHOT_EXTENSION))
+            .unwrap_or_default();
+        let Some(output) = FileDialog::new()
+            .add_filter("Snapshot", &[SNAPSHOT_EXTENSION])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        if let Err(error) = create_snapshot(listing_path, None, None, &self.config.parser).and_then(|snapshot| write_snapshot(&snapshot, &output)) {
+            self.error_message = Some(format!("Failed to save the snapshot: {}", error));
+        }
+    }
+
+    /// Results of the current comparison for export, None before a calculation
+    fn report(&self) -> Option<Report<'_>> {
+        Some(Report {
+            file_name: self.file1_name.as_deref().unwrap_or("File 1 name not found"),
+            comparison: self.comparison.as_ref()?,
+            listing1: &self.listing1,
+            listing2: &self.listing2,
+            source_lines: &self.source_lines,
+        })
+    }
+
+    /// Writes the current comparison as a self-contained HTML page chosen by the user
+    fn export_html(&mut self) {
+        let Some(output) = FileDialog::new().add_filter("HTML", &["html"]).set_file_name("report.html").save_file() else {
+            return;
+        };
+
+        let result = self.report().map_or(Ok(()), |report| report.write_html(&output));
+        if let Err(error) = result {
+            self.error_message = Some(format!("Failed to write the report: {}", error));
+        }
+    }
+
+    /// Remapping sources & source root of the selected pair
+    ///
+    /// The listings of the last git comparison use the copies of the sources at both revisions & the listings of the
+    /// last compile use the directory of the compiled source as root, any other pair uses the selected sources & root.
+    fn comparison_sources(&self) -> (Option<(std::path::PathBuf, std::path::PathBuf)>, Option<std::path::PathBuf>) {
+        let selected_sources = self.source1_path.clone().zip(self.source2_path.clone());
+        match (&self.revisions, &self.temp_dir) {
+            (Some(revisions), _)
+                if self.file1_path.as_ref() == Some(&revisions.listing_a)
+                    && self.file2_path.as_ref() == Some(&revisions.listing_b) =>
+            {
+                (Some((revisions.source_a.clone(), revisions.source_b.clone())), Some(revisions.source_root()))
+            }
+            (_, Some(dir)) if self.file1_path.as_ref().is_some_and(|path| path.starts_with(dir.path())) => {
+                (selected_sources, self.compiled_root.clone())
+            }
+            _ => (selected_sources, self.source_root.clone()),
+        }
+    }
+
+    /// Compares the two selected files & stores the results for display
+    fn calculate(&mut self) {
+        self.error_message = None;
+        let (sources, source_root) = self.comparison_sources();
+
+        if let (Some(file1_path), Some(file2_path)) = (&self.file1_path, &self.file2_path) {
+            match (load_input(file1_path, &self.config.parser), load_input(file2_path, &self.config.parser)) {
+                (Ok(listing1), Ok(mut listing2)) => {
+                    if let Some((source1_path, source2_path)) = &sources {
+                        match remap_listing(&listing2, source1_path, source2_path) {
+                            Ok(remapped) => listing2 = remapped,
+                            Err(error) => {
+                                // The keys of the two listings would be paired wrongly without the remapping
+                                self.error_message = Some(format!("Failed to read the source files: {}", error));
+                                return;
+                            }
+                        }
+                    }
+
+                    if let (Some(name1), Some(name2)) = (listing1.file_name.as_deref(), listing2.file_name.as_deref()) {
+                        self.file1_name = Some(name1.to_string());
+                        self.file2_name = Some(name2.to_string());
+                    }
+
+                    let comparison = compare_listings(&listing1, &listing2, &self.compare_options());
+
+                    // Highlight what changed when the same pair is compared again
+                    let same_pair = self.compared_pair.as_ref().is_some_and(|(file1, file2)| file1 == file1_path && file2 == file2_path);
+                    self.changed_keys = match (&self.comparison, same_pair) {
+                        (Some(previous), true) => changed_keys(previous, &comparison),
+                        _ => HashSet::new(),
+                    };
+                    let roots = source_roots(source_root.as_deref(), file1_path);
+                    self.source_lines = load_source_lines(comparison.results.iter().map(|result| result.key.as_str()), &roots);
+                    self.comparison = Some(comparison);
+                    self.compared_pair = Some((file1_path.clone(), file2_path.clone()));
+                    self.listing1 = listing1;
+                    self.listing2 = listing2;
+                    self.selected_key = None;
+                    // Generated listings are removed with their temp directory, there is nothing to open again
+                    let generated = [self.temp_dir.as_ref().map(TempDir::path), self.revisions.as_ref().map(|revisions| revisions.dir.path())]
+                        .into_iter()
+                        .flatten()
+                        .any(|dir| file1_path.starts_with(dir));
+                    if !generated {
+                        push_recent(
+                            &mut self.recent,
+                            RecentComparison {
+                                file1: file1_path.clone(),
+                                file2: file2_path.clone(),
+                                source1: self.source1_path.clone(),
+                                source2: self.source2_path.clone(),
+                                source_root: self.source_root.clone(),
+                            },
+                        );
+                    }
+                }
+                _ => self.error_message = Some("Failed to read one or both files.".to_string()),
+            }
+        } else {
+            self.error_message = Some("Please select both files.".to_string());
+        }
     }
 }
 
 impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.session_state());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-       
-        let mut map1 = HashMap::new();
-        let mut map2 = HashMap::new();
+        self.poll_watcher(ctx);
+
+        // Files & directories dropped onto the window
+        let dropped: Vec<std::path::PathBuf> =
+            ctx.input(|input| input.raw.dropped_files.iter().filter_map(|file| file.path.clone()).collect());
+        if !dropped.is_empty() {
+            self.drop_paths(dropped);
+        }
+        if ctx.input(|input| !input.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_target")));
+            let screen_rect = ctx.screen_rect();
+            painter.rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(192));
+            painter.text(
+                screen_rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop one or two listings, or one or two directories",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("Recent comparisons", |ui| {
+                    if self.recent.is_empty() {
+                        ui.weak("No comparisons yet");
+                    }
+                    let mut opened = None;
+                    for recent in &self.recent {
+                        let tooltip = format!("{}\n{}", recent.file1.display(), recent.file2.display());
+                        if ui.button(recent.label()).on_hover_text(tooltip).clicked() {
+                            opened = Some(recent.clone());
+                        }
+                    }
+                    if let Some(recent) = opened {
+                        self.open_recent(recent);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.add_enabled(!self.recent.is_empty(), egui::Button::new("Clear")).clicked() {
+                        self.recent.clear();
+                        ui.close_menu();
+                    }
+                });
+                if ui.button("Reset").clicked() {
+                    self.reset();
+                }
+            });
+        });
+
+        // Diff view of the key selected in the results table
+        if let Some(key) = self.selected_key.clone() {
+            egui::SidePanel::right("diff_view")
+                .resizable(true)
+                .default_width(400.0)
+                .show(ctx, |ui| {
+                    if ui.button("Close").clicked() {
+                        self.selected_key = None;
+                    }
+                    diff_view::show_block_diff(
+                        ui,
+                        &key,
+                        self.listing1.blocks.get(&key).map(|block| block.as_str()),
+                        self.listing2.blocks.get(&key).map(|block| block.as_str()),
+                        self.source_lines.get(&key).map(|line| line.as_str()),
+                        self.config.parser.dialect,
+                    );
+                });
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Dissimilarity Metrics Calculator");
+            ui.weak("Select the files below or drop one or two listings, or two directories, onto the window.");
 
             // File 1 selection
             if ui.button("Select File 1").clicked() {
@@ -73,8 +928,13 @@ impl eframe::App for MyApp {
                 }
             }
 
-            if let Some(path) = &self.file1_path {
-                ui.label(format!("File 1: {}", path.display()));
+            if let Some(path) = self.file1_path.clone() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("File 1: {}", path.display()));
+                    if !is_snapshot(&path) && ui.button("Save Snapshot").clicked() {
+                        self.save_snapshot(&path);
+                    }
+                });
             }
 
             ui.separator();
@@ -87,114 +947,249 @@ impl eframe::App for MyApp {
                 }
             }
 
-            if let Some(path) = &self.file2_path {
-                ui.label(format!("File 2: {}", path.display()));
+            if let Some(path) = self.file2_path.clone() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("File 2: {}", path.display()));
+                    if !is_snapshot(&path) && ui.button("Save Snapshot").clicked() {
+                        self.save_snapshot(&path);
+                    }
+                });
             }
 
             ui.separator();
 
-            // Calculate Levenshtein Distance
-            if ui.button("Calculate Levenshtein Distance").clicked() {
-                println!("Calculate button clicked!");
-
-                if let (Some(file1_path), Some(file2_path)) = (&self.file1_path, &self.file2_path) {
-                    println!("Both files selected.");
-
-                    match (read_binary_file(file1_path.to_str().unwrap()), read_binary_file(file2_path.to_str().unwrap())) {
-                        (Ok(data1), Ok(data2)) => {
-                            println!("Files read successfully.");
+            // Batch comparison of two output trees
+            ui.collapsing("Batch comparison", |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Select Directory 1").clicked() {
+                        if let Some(path) = FileDialog::new().pick_folder() {
+                            self.batch_dir1 = Some(path);
+                        }
+                    }
+                    if let Some(path) = &self.batch_dir1 {
+                        ui.label(format!("Directory 1: {}", path.display()));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Select Directory 2").clicked() {
+                        if let Some(path) = FileDialog::new().pick_folder() {
+                            self.batch_dir2 = Some(path);
+                        }
+                    }
+                    if let Some(path) = &self.batch_dir2 {
+                        ui.label(format!("Directory 2: {}", path.display()));
+                    }
+                });
+                if ui.button("Compare Directories").clicked() {
+                    self.calculate_batch();
+                }
+                self.show_batch(ui);
+            });
 
-                            let file_name1 = extract_file_name(&data1);
-                            let file_name2 = extract_file_name(&data2);
+            ui.separator();
 
-                            match (file_name1.as_deref(), file_name2.as_deref()) {
-                                (Some(name1), Some(name2)) => {
-                                    self.file1_name = Some(name1.to_string());
-                                    self.file2_name = Some(name2.to_string());
-                                    println!("File names extracted: {} and {}", name1, name2);
-                                }
-                                _ => println!("One or both file names not found."),
+            // Build both listings from one source file
+            ui.collapsing("Compile and compare", |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Select Source File").clicked() {
+                        if let Some(path) = FileDialog::new().add_filter("C/C++ source", &["c", "cc", "cpp", "cxx"]).pick_file() {
+                            self.compile_source = Some(path);
+                        }
+                    }
+                    if let Some(path) = &self.compile_source {
+                        ui.label(format!("Source: {}", path.display()));
+                    }
+                });
+                egui::Grid::new("compile_sides").show(ui, |ui| {
+                    ui.label("Side A");
+                    egui::ComboBox::from_id_source("compiler_a")
+                        .selected_text(&self.compiler_a)
+                        .show_ui(ui, |ui| {
+                            for compiler in &self.compilers {
+                                ui.selectable_value(&mut self.compiler_a, compiler.clone(), compiler);
                             }
+                        });
+                    ui.text_edit_singleline(&mut self.compile_flags_a);
+                    ui.end_row();
 
-                            // Generate hashmaps for both files
-                            if let Some(file_name1) = &file_name1 {
-                                (map1, self.file1_text) = generate_hashmap_srcRefBlock(&data1, file_name1);
-                               println!("{:?}", self.file1_text);
+                    ui.label("Side B");
+                    egui::ComboBox::from_id_source("compiler_b")
+                        .selected_text(&self.compiler_b)
+                        .show_ui(ui, |ui| {
+                            for compiler in &self.compilers {
+                                ui.selectable_value(&mut self.compiler_b, compiler.clone(), compiler);
                             }
+                        });
+                    ui.text_edit_singleline(&mut self.compile_flags_b);
+                    ui.end_row();
+                });
+                if ui.button("Compile and Compare").clicked() {
+                    self.compile_and_compare();
+                }
 
-                            if let Some(file_name2) = &file_name2 {
-                                (map2, self.file2_text) = generate_hashmap_srcRefBlock(&data2, file_name2);
-                               println!("{:?}", self.file2_text);
-                            }
+                // Whole project from a compilation database, with the flags above added to every command
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Select compile_commands.json").clicked() {
+                        if let Some(path) = FileDialog::new().add_filter("Compilation database", &["json"]).pick_file() {
+                            self.compile_commands = Some(path);
+                        }
+                    }
+                    if let Some(path) = &self.compile_commands {
+                        ui.label(format!("Database: {}", path.display()));
+                    }
+                });
+                if ui.button("Compile Project and Compare").clicked() {
+                    self.compile_project_and_compare();
+                }
+                ui.weak("The project totals are shown under Batch comparison.");
+            });
 
-                            // Clear previous results
-                            self.results.clear();
-
-                            // Perform calculations and store the results
-                            for (key, value1) in &map1 {
-                                if let Some(value2) = map2.get(key) {
-                                    let distance = optimized_levenshtein(value1.as_bytes(), value2.as_bytes());
-                                    self.levenshtein_distance += distance as i32;
-
-                                    let (padded_str1, padded_str2) = pad_strings(value1, value2);
-                                    let similarity = cosine_similarity(&padded_str1, &padded_str2);
-
-                                    let max_file_len = value1.len().max(value2.len()) as f64;
-                                    self.file_length += max_file_len;
-                                    let change_percent = (distance as f64 / max_file_len) * 100.0;
-                                    
-                                    // Store results in the vector
-                                    self.results.push((
-                                        key.clone(),
-                                        distance as i32,
-                                        max_file_len as i32,
-                                        similarity,
-                                        change_percent,
-                                    ));
-                                } else {
-                                    // Handle missing key in map2
-                                    self.results.push((
-                                        key.clone(),
-                                        -1, // Indicate missing value with a sentinel
-                                        -1,
-                                        -1.0,
-                                        -1.0,
-                                    ));
-                                }
-                            }
+            ui.separator();
 
-                            for (key, _value) in &map2 {
-                                if !map1.contains_key(key) {
-                                    // Handle missing key in map1
-                                    self.results.push((
-                                        key.clone(),
-                                        -1, // Indicate missing value with a sentinel
-                                        -1,
-                                        -1.0,
-                                        -1.0,
-                                    ));
-                                }
-                            }
+            // Same file built at two revisions of a git repository
+            ui.collapsing("Git revisions", |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Select Repository").clicked() {
+                        if let Some(path) = FileDialog::new().pick_folder() {
+                            self.git_repo = Some(path);
+                        }
+                    }
+                    if let Some(path) = &self.git_repo {
+                        ui.label(format!("Repository: {}", path.display()));
+                    }
+                });
+                egui::Grid::new("git_options").show(ui, |ui| {
+                    ui.label("Revision A");
+                    ui.text_edit_singleline(&mut self.git_revision_a);
+                    ui.end_row();
+                    ui.label("Revision B");
+                    ui.text_edit_singleline(&mut self.git_revision_b);
+                    ui.end_row();
+                    ui.label("Source file");
+                    ui.text_edit_singleline(&mut self.git_file);
+                    ui.end_row();
+                    ui.label("Compile command");
+                    ui.text_edit_singleline(&mut self.git_command);
+                    ui.end_row();
+                });
+                if ui.button("Compare Revisions").clicked() {
+                    self.compare_revisions();
+                }
+            });
+
+            ui.separator();
+
+            // N-way comparison of the same translation unit built with different options
+            ui.collapsing("Comparison matrix", |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Select Listings").clicked() {
+                        if let Some(paths) = FileDialog::new().pick_files() {
+                            self.matrix_paths = paths;
+                        }
+                    }
+                    ui.label(format!("{} listing(s) selected", self.matrix_paths.len()));
+                });
+                if ui.button("Compare All Pairs").clicked() {
+                    self.error_message = None;
+                    if self.matrix_paths.len() < 2 {
+                        self.error_message = Some("Please select at least two listings.".to_string());
+                    } else {
+                        match compare_matrix(&self.matrix_paths, &self.compare_options()) {
+                            Ok(matrix) => self.matrix = Some(matrix),
+                            Err(error) => self.error_message = Some(format!("Failed to compare the listings: {}", error)),
+                        }
+                    }
+                }
+                self.show_matrix(ui);
+            });
+
+            ui.separator();
 
-                            self.should_display_output = true; // Set the flag to true when calculation is done
-                            
-                            let (padded_str1, padded_str2) = pad_strings(&self.file1_text, &self.file2_text);
-                            self.cosine_similarity = cosine_similarity(&padded_str1, &padded_str2);
-                            //println!("Cosine of file {}",similarity);
-                            self.change_percentage = (self.levenshtein_distance as f64 / self.file_length) * 100.0;
-                            // println!("% change {}",change_percent);
-                            println!("Calculation complete.");
+            // Source root used to show the source line of every key
+            ui.horizontal(|ui| {
+                if ui.button("Select Source Root").clicked() {
+                    if let Some(path) = FileDialog::new().pick_folder() {
+                        self.source_root = Some(path);
+                    }
+                }
+                match &self.source_root {
+                    Some(path) => ui.label(format!("Source root: {}", path.display())),
+                    None => ui.weak("Sources are searched next to File 1"),
+                };
+            });
+
+            ui.separator();
+
+            // Optional source files to match blocks by corresponding source line instead of raw line number
+            ui.collapsing("Source remapping", |ui| {
+                ui.label("Select both versions of the source file to re-key File 2 through a line diff.");
+                ui.horizontal(|ui| {
+                    if ui.button("Select Source 1").clicked() {
+                        if let Some(path) = FileDialog::new().pick_file() {
+                            self.source1_path = Some(path);
                         }
-                        _ => {
-                            self.error_message = Some("Failed to read one or both files.".to_string());
-                            println!("Failed to read files.");
+                    }
+                    if let Some(path) = &self.source1_path {
+                        ui.label(format!("Source 1: {}", path.display()));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Select Source 2").clicked() {
+                        if let Some(path) = FileDialog::new().pick_file() {
+                            self.source2_path = Some(path);
                         }
                     }
-                } else {
-                    self.error_message = Some("Please select both files.".to_string());
-                    println!("Files not selected.");
+                    if let Some(path) = &self.source2_path {
+                        ui.label(format!("Source 2: {}", path.display()));
+                    }
+                });
+                if ui.button("Clear sources").clicked() {
+                    self.source1_path = None;
+                    self.source2_path = None;
                 }
-            }
+            });
+
+            ui.separator();
+
+            // Skip the exact distance of blocks that are obviously over the threshold
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.use_max_distance, "Skip blocks with distance over");
+                ui.add_enabled(
+                    self.use_max_distance,
+                    egui::DragValue::new(&mut self.max_distance).clamp_range(0..=100_000),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Threads (0 = all cores)");
+                ui.add(egui::DragValue::new(&mut self.threads).clamp_range(0..=256));
+            });
+
+            ui.collapsing("Settings", |ui| self.show_settings(ui));
+
+            ui.separator();
+
+            // Calculate Levenshtein Distance
+            ui.horizontal(|ui| {
+                if ui.button("Calculate Levenshtein Distance").clicked() {
+                    self.calculate();
+                }
+                let mut watching = self.watcher.is_some();
+                if ui.checkbox(&mut watching, "Watch files").on_hover_text("Compare again when a selected file changes on disk").changed() {
+                    self.watcher = watching.then(|| FileWatcher::new(self.watched_paths()));
+                }
+                if ui.add_enabled(self.comparison.is_some(), egui::Button::new("Export HTML Report")).clicked() {
+                    self.export_html();
+                }
+                if ui.add_enabled(self.comparison.is_some(), egui::Button::new("Copy Markdown Report")).clicked() {
+                    if let Some(markdown) = self.report().map(|report| report.to_markdown(self.markdown_top)) {
+                        ui.output_mut(|output| output.copied_text = markdown);
+                    }
+                }
+                ui.label("Top keys");
+                ui.add(egui::DragValue::new(&mut self.markdown_top).clamp_range(1..=100));
+            });
 
             // Add space after the Calculate button
             ui.add_space(10.0);
@@ -229,58 +1224,246 @@ impl eframe::App for MyApp {
             // Add space before the results table
             ui.add_space(10.0);
 
-            // Display the results in a table if the flag is set
-            if self.should_display_output {
+            // Display the results in a table once a calculation is done
+            if let Some(comparison) = &self.comparison {
+                let levenshtein = self.config.metrics.is_enabled("levenshtein");
+                let cosine = self.config.metrics.is_enabled("cosine");
+                let change = self.config.metrics.is_enabled("change");
+                let divergence = self.config.metrics.is_enabled("divergence");
+                let classes = self.config.metrics.is_enabled("classes");
+                let weighted = self.config.metrics.is_enabled("weighted");
+                let diff = self.config.metrics.is_enabled("diff");
+                let damerau = self.config.metrics.is_enabled("damerau");
+                let similarity_metrics = self.config.metrics.similarity_metrics();
+
                 // First table with summary results
                 egui::Grid::new("summary_table")
                     .striped(true)
                     .min_col_width(150.0)
                     .show(ui, |ui| {
                         ui.label("File Name");
-                        ui.label("Levenshtein Distance");
-                        ui.label("Cosine Similarity");
-                        ui.label("Change %");
+                        ui.label("Scope");
+                        if levenshtein {
+                            ui.label("Levenshtein Distance");
+                        }
+                        if weighted {
+                            ui.label("Weighted Distance");
+                        }
+                        if cosine {
+                            ui.label("Cosine Similarity");
+                        }
+                        if change {
+                            ui.label("Change %");
+                        }
+                        if divergence {
+                            ui.label("JS Divergence");
+                        }
+                        for metric in &similarity_metrics {
+                            ui.label(metric.label());
+                        }
+                        ui.label("Derivation");
                         ui.end_row();
 
-                        // Display file 1 results
-                        ui.label(self.file1_name.as_deref().unwrap_or("File 1 name not found"));
-                        ui.label(format!("{}", self.levenshtein_distance));
-                        ui.label(format!("{:.2}", self.cosine_similarity));
-                        ui.label(format!("{:.2}%", self.change_percentage));
-                        ui.end_row();
+                        // Matched-only totals first, then the totals including unmatched blocks
+                        for (index, row) in comparison.summary_rows().into_iter().enumerate() {
+                            if index == 0 {
+                                ui.label(self.file1_name.as_deref().unwrap_or("File 1 name not found"));
+                            } else {
+                                ui.label("");
+                            }
+                            ui.label(row.scope);
+                            if levenshtein {
+                                ui.label(row.distance);
+                            }
+                            if weighted {
+                                ui.label(row.weighted_distance);
+                            }
+                            if cosine {
+                                ui.label(format!("{:.2}", comparison.cosine_similarity));
+                            }
+                            if change {
+                                ui.label(row.change);
+                            }
+                            if divergence {
+                                ui.label(format!("{:.3}", comparison.divergence));
+                            }
+                            for metric in &similarity_metrics {
+                                ui.label(format!("{:.3}", row.similarities.get(*metric)));
+                            }
+                            ui.weak(row.derivation);
+                            ui.end_row();
+                        }
                     });
 
+                if classes {
+                    ui.label(format!(
+                        "Instruction classes: {} (class distance {} over matched keys)",
+                        comparison.class_changes, comparison.class_distance
+                    ));
+                }
+                if diff {
+                    ui.label(format!(
+                        "Diff: {} changed line(s) in {} hunk(s), {} unchanged (LCS) over matched keys",
+                        comparison.changed_lines, comparison.hunks, comparison.lcs_length
+                    ));
+                }
+                if damerau {
+                    ui.label(format!(
+                        "Reordering: {} edit(s) saved by adjacent swaps, Damerau-Levenshtein {} (OSA {}) over matched keys",
+                        comparison.swaps, comparison.damerau_distance, comparison.osa_distance
+                    ));
+                }
+
+                // Opcode & instruction class counts of all blocks of both listings
+                ui.collapsing("Opcode histogram", |ui| {
+                    let histogram1 = listing_histogram(&self.listing1);
+                    let histogram2 = listing_histogram(&self.listing2);
+                    let dialect = self.config.parser.dialect;
+                    show_histograms(
+                        ui,
+                        "listing_class_histogram",
+                        &class_label_histogram(&class_histogram(&histogram1, dialect)),
+                        &class_label_histogram(&class_histogram(&histogram2, dialect)),
+                    );
+                    ui.separator();
+                    show_histograms(ui, "listing_histogram", &histogram1, &histogram2);
+                });
+
+                // Result of the configured thresholds
+                if !self.config.thresholds.is_empty() {
+                    let violations = check_comparison(comparison, &self.config.thresholds);
+                    if violations.is_empty() {
+                        ui.colored_label(egui::Color32::from_rgb(90, 180, 90), "Thresholds: PASS");
+                    } else {
+                        ui.collapsing(
+                            egui::RichText::new(format!("Thresholds: FAIL ({} violation(s))", violations.len()))
+                                .color(egui::Color32::from_rgb(220, 90, 90)),
+                            |ui| {
+                                for violation in &violations {
+                                    match &violation.key {
+                                        Some(key) => ui.label(format!("{}: {}", key, violation.message)),
+                                        None => ui.label(&violation.message),
+                                    };
+                                }
+                            },
+                        );
+                    }
+                }
+
                 // Add space between the tables
                 ui.add_space(20.0);
 
-                egui::Grid::new("dissimilarity_metrics_table")
-                    .striped(true)
-                    .min_col_width(100.0)
-                    .show(ui, |ui| {
-                        ui.label("Key");
-                        ui.label("Levenshtein Distance");
-                        ui.label("Cosine Similarity");
-                        ui.label("Block Length");
-                        ui.label("Change %");
-                        ui.end_row();
-
-                        for (key, distance,block_length, similarity, change_percent) in &self.results {
-                            if *distance != -1 {
-                                ui.label(key);
-                                ui.label(format!("{}", distance));
-                                ui.label(format!("{:.2}", similarity));
-                                ui.label(format!("{:.2}", block_length));
-                                ui.label(format!("{:.2}%", change_percent));
-                            } else {
-                                ui.label(key);
-                                ui.label("N/A");
-                                ui.label("N/A");
-                                ui.label("N/A");
-                                ui.label("N/A");
+                ui.label("Click a key to open its instructions side by side.");
+                if !self.changed_keys.is_empty() {
+                    ui.label(
+                        egui::RichText::new(format!("{} key(s) changed since the previous run", self.changed_keys.len()))
+                            .background_color(CHANGED_KEY_COLOR),
+                    );
+                }
+                egui::ScrollArea::vertical().id_source("dissimilarity_metrics_scroll").show(ui, |ui| {
+                    egui::Grid::new("dissimilarity_metrics_table")
+                        .striped(true)
+                        .min_col_width(100.0)
+                        .show(ui, |ui| {
+                            let (column, ascending) = (&mut self.sort_column, &mut self.sort_ascending);
+                            sort_header(ui, "Key", SortColumn::Key, column, ascending);
+                            if levenshtein {
+                                sort_header(ui, "Levenshtein Distance", SortColumn::Distance, column, ascending);
+                            }
+                            if weighted {
+                                sort_header(ui, "Weighted Distance", SortColumn::WeightedDistance, column, ascending);
+                            }
+                            if cosine {
+                                sort_header(ui, "Cosine Similarity", SortColumn::Similarity, column, ascending);
                             }
+                            sort_header(ui, "Block Length", SortColumn::BlockLength, column, ascending);
+                            if change {
+                                sort_header(ui, "Change %", SortColumn::Change, column, ascending);
+                            }
+                            if divergence {
+                                sort_header(ui, "JS Divergence", SortColumn::Divergence, column, ascending);
+                            }
+                            if classes {
+                                sort_header(ui, "Class Distance", SortColumn::ClassDistance, column, ascending);
+                            }
+                            if diff {
+                                sort_header(ui, "LCS", SortColumn::Lcs, column, ascending);
+                                sort_header(ui, "Hunks", SortColumn::Hunks, column, ascending);
+                                sort_header(ui, "Changed Lines", SortColumn::ChangedLines, column, ascending);
+                            }
+                            if damerau {
+                                sort_header(ui, "OSA", SortColumn::Osa, column, ascending);
+                                sort_header(ui, "Damerau", SortColumn::Damerau, column, ascending);
+                                sort_header(ui, "Swaps", SortColumn::Swaps, column, ascending);
+                            }
+                            for metric in &similarity_metrics {
+                                sort_header(ui, metric.label(), SortColumn::Normalized(*metric), column, ascending);
+                            }
+                            if classes {
+                                ui.label("Class Changes");
+                            }
+                            ui.label("Source");
                             ui.end_row();
-                        }
-                    });
+
+                            for result in sorted_results(&comparison.results, self.sort_column, self.sort_ascending) {
+                                // Keys without a source line are greyed out
+                                let source = self.source_lines.get(&result.key);
+                                let mut key_text = match source {
+                                    Some(_) => egui::RichText::new(&result.key),
+                                    None => egui::RichText::new(&result.key).weak(),
+                                };
+                                // Keys that changed since the previous run are highlighted
+                                if self.changed_keys.contains(&result.key) {
+                                    key_text = key_text.background_color(CHANGED_KEY_COLOR);
+                                }
+                                let selected = self.selected_key.as_ref() == Some(&result.key);
+                                if ui.selectable_label(selected, key_text).clicked() {
+                                    self.selected_key = Some(result.key.clone());
+                                }
+
+                                if levenshtein {
+                                    ui.label(result.distance_label());
+                                }
+                                if weighted {
+                                    ui.label(result.weighted_distance_label());
+                                }
+                                if cosine {
+                                    ui.label(result.similarity_label());
+                                }
+                                ui.label(format!("{}", result.block_length));
+                                if change {
+                                    ui.label(result.change_label());
+                                }
+                                if divergence {
+                                    ui.label(result.divergence_label());
+                                }
+                                if classes {
+                                    ui.label(result.class_distance_label());
+                                }
+                                if diff {
+                                    ui.label(result.count_label(result.lcs_length));
+                                    ui.label(result.count_label(result.hunks));
+                                    ui.label(result.count_label(result.changed_lines));
+                                }
+                                if damerau {
+                                    ui.label(result.count_label(result.osa_distance));
+                                    ui.label(result.count_label(result.damerau_distance));
+                                    ui.label(result.count_label(result.swaps));
+                                }
+                                for metric in &similarity_metrics {
+                                    ui.label(result.similarity_of_label(*metric));
+                                }
+                                if classes {
+                                    ui.label(&result.class_changes);
+                                }
+                                match source {
+                                    Some(source) => ui.add(egui::Label::new(egui::RichText::new(source).monospace()).truncate(true)),
+                                    None => ui.weak("-"),
+                                };
+                                ui.end_row();
+                            }
+                        });
+                });
                     
             }
             
@@ -295,12 +1478,24 @@ impl eframe::App for MyApp {
 
 
 fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match cli::run(&args) {
+            Ok(0) => return Ok(()),
+            Ok(code) => std::process::exit(code),
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
     let options = eframe::NativeOptions::default();
     let _ = eframe::run_native(
 
         "Disimilarity Metrics",
         options,
-        Box::new(|_cc| Box::new(MyApp::default())),
+        Box::new(|cc| Box::new(MyApp::new(cc))),
     );
 //     let file1 = "/home/cbq2kor/Desktop/DevSpace/Test/RDS/C/Cpp/Multiplication/operation_fverb_o1.asm";
 //     let file2 = "/home/cbq2kor/Desktop/DevSpace/Test/RDS/C/Cpp/Multiplication/operation_fverb_o2.asm";
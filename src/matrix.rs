@@ -0,0 +1,101 @@
+use std::{
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{compare::*, input::*, parser::*};
+
+/// Whole-file metrics of one pair of listings
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatrixCell {
+    pub distance: i32,
+    pub cosine_similarity: f64,
+    pub change_percentage: f64,
+}
+
+/// Metrics of every pair of N listings, `cells[i][j]` compares listing i with listing j
+#[derive(Debug, Clone, Default)]
+pub struct ComparisonMatrix {
+    pub paths: Vec<PathBuf>,
+    pub labels: Vec<String>,
+    pub cells: Vec<Vec<MatrixCell>>,
+}
+
+/// Short label of a listing for the matrix headers
+fn matrix_label(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string())
+}
+
+/// Compares every pair of the given listings
+///
+/// The overall totals are used, so blocks that exist in only one listing count as full insertions or deletions.
+/// All the metrics are symmetric, so only one comparison is done per pair.
+pub fn compare_matrix(paths: &[PathBuf], options: &CompareOptions) -> Result<ComparisonMatrix> {
+    let listings = paths.iter().map(|path| load_input(path, &options.parser)).collect::<Result<Vec<Listing>>>()?;
+    let count = listings.len();
+
+    let mut cells = vec![vec![MatrixCell::default(); count]; count];
+    for i in 0..count {
+        cells[i][i].cosine_similarity = 1.0;
+        for j in (i + 1)..count {
+            let comparison = compare_listings(&listings[i], &listings[j], options);
+            let cell = MatrixCell {
+                distance: comparison.overall_distance,
+                cosine_similarity: comparison.cosine_similarity,
+                change_percentage: comparison.overall_change_percentage,
+            };
+            cells[i][j] = cell;
+            cells[j][i] = cell;
+        }
+    }
+
+    Ok(ComparisonMatrix {
+        paths: paths.to_vec(),
+        labels: paths.iter().map(|path| matrix_label(path)).collect(),
+        cells,
+    })
+}
+
+/// Metric shown in the matrix heatmap
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MatrixMetric {
+    Distance,
+    CosineSimilarity,
+    ChangePercentage,
+}
+
+impl MatrixMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MatrixMetric::Distance => "Levenshtein Distance",
+            MatrixMetric::CosineSimilarity => "Cosine Similarity",
+            MatrixMetric::ChangePercentage => "Change %",
+        }
+    }
+
+    pub fn label(&self, cell: &MatrixCell) -> String {
+        match self {
+            MatrixMetric::Distance => format!("{}", cell.distance),
+            MatrixMetric::CosineSimilarity => format!("{:.2}", cell.cosine_similarity),
+            MatrixMetric::ChangePercentage => format!("{:.2}%", cell.change_percentage),
+        }
+    }
+
+    /// How different the pair is on a 0 (identical) to 1 (most different in the matrix) scale
+    pub fn heat(&self, cell: &MatrixCell, matrix: &ComparisonMatrix) -> f32 {
+        match self {
+            MatrixMetric::Distance => {
+                let max = matrix.cells.iter().flatten().map(|cell| cell.distance).max().unwrap_or(0);
+                if max > 0 {
+                    cell.distance as f32 / max as f32
+                } else {
+                    0.0
+                }
+            }
+            MatrixMetric::CosineSimilarity => (1.0 - cell.cosine_similarity as f32).clamp(0.0, 1.0),
+            MatrixMetric::ChangePercentage => (cell.change_percentage as f32 / 100.0).clamp(0.0, 1.0),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use core::str;
 use regex::Regex;
-use std::{cell::OnceCell, collections::HashMap, error, fs::File, io::{Read, Result}};
+use serde::{Deserialize, Serialize};
+use std::{cell::OnceCell, collections::HashMap, error, fs::File, io::{Error, ErrorKind, Read, Result}, path::Path};
 
 use nom::{
     bytes::complete::{tag, take_until, take_while1},
@@ -170,6 +171,154 @@ pub fn read_binary_file(file_path: &str) -> Result<Vec<u8>> {
     Ok(buffer)
 }
 
+/// Assembler syntax of the listing, decides how the source reference comments start
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    /// GNU as for x86 & x86-64, `# file.c:12:`
+    #[default]
+    X86,
+    /// GNU as for AArch64, `// file.c:12:`
+    Aarch64,
+    /// GNU as for 32-bit ARM, `@ file.c:12:`
+    Arm,
+}
+
+impl Dialect {
+    pub const ALL: [Dialect; 3] = [Dialect::X86, Dialect::Aarch64, Dialect::Arm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::X86 => "x86",
+            Dialect::Aarch64 => "aarch64",
+            Dialect::Arm => "arm",
+        }
+    }
+
+    /// Comment marker of the `-fverbose-asm` source references
+    fn comment(&self) -> &'static str {
+        match self {
+            Dialect::X86 => "#",
+            Dialect::Aarch64 => "//",
+            Dialect::Arm => "@",
+        }
+    }
+}
+
+/// Matches the `-fverbose-asm` source reference comments of the dialect, e.g. `# file.c:12:`
+fn source_reference_regex(dialect: Dialect) -> Regex {
+    Regex::new(&format!(r#"(?m)^{}\s*([\w/.\+-]+):(\d+):"#, regex::escape(dialect.comment()))).unwrap()
+}
+
+/// Instruction prefixes dropped by `strip_prefixes`, the instruction they apply to is kept
+const INSTRUCTION_PREFIXES: [&str; 8] = ["lock", "rep", "repe", "repz", "repne", "repnz", "notrack", "bnd"];
+
+/// Switches that make equivalent instructions compare as equal
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Normalization {
+    /// Compare mnemonics case-insensitively
+    pub lowercase: bool,
+    /// Drop prefixes such as `lock` & `rep` & keep the instruction they apply to
+    pub strip_prefixes: bool,
+}
+
+/// How the blocks of a listing are extracted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParserOptions {
+    pub dialect: Dialect,
+    /// Keep assembler directives such as `.p2align` in the blocks, they are dropped by default
+    pub keep_directives: bool,
+    /// Directives left out even when `keep_directives` is set, a trailing `*` matches a prefix, e.g. `.cfi_*`
+    pub ignored_directives: Vec<String>,
+    /// Mnemonics left out of the blocks, e.g. `nop` or `endbr64`, a trailing `*` matches a prefix
+    pub ignored_mnemonics: Vec<String>,
+    pub normalize: Normalization,
+}
+
+/// Whether a token matches one of the patterns, exactly or by prefix for patterns ending with `*`
+fn matches_any(token: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => token.starts_with(prefix),
+        None => token == pattern,
+    })
+}
+
+impl ParserOptions {
+    /// Mnemonic or directive kept from an instruction line, None when the line is left out
+    fn instruction(&self, line: &str) -> Option<String> {
+        let mut tokens = line.split_whitespace();
+        let mut token = tokens.next().unwrap_or("").to_string();
+        if self.normalize.lowercase {
+            token = token.to_lowercase();
+        }
+        if self.normalize.strip_prefixes {
+            while INSTRUCTION_PREFIXES.contains(&token.to_lowercase().as_str()) {
+                match tokens.next() {
+                    Some(next) if self.normalize.lowercase => token = next.to_lowercase(),
+                    Some(next) => token = next.to_string(),
+                    None => break,
+                }
+            }
+        }
+
+        if token.starts_with(char::is_numeric) || token.starts_with('_') {
+            return None;
+        }
+        // Local labels such as `.L3:` are never kept
+        if token.starts_with('.') {
+            let kept = self.keep_directives && !token.ends_with(':') && !matches_any(&token, &self.ignored_directives);
+            return kept.then_some(token);
+        }
+        (!matches_any(&token, &self.ignored_mnemonics)).then_some(token)
+    }
+}
+
+/// Parsed assembly listing ready for comparison
+#[derive(Debug, Clone, Default)]
+pub struct Listing {
+    pub file_name: Option<String>,
+    pub blocks: HashMap<String, String>,
+    pub text: String,
+}
+
+/// Reads an assembly listing from disk & splits it into source reference blocks
+///
+/// The blocks are only generated when the `.file` directive is found, otherwise the listing is returned empty
+/// with `file_name` set to None. Use `load_input` for files that may also be snapshots.
+pub fn load_listing(path: &Path, options: &ParserOptions) -> Result<Listing> {
+    let data = read_binary_file(&path.to_string_lossy())?;
+    let file_name = extract_file_name(&data);
+    let mut listing = Listing {
+        file_name: file_name.clone(),
+        ..Default::default()
+    };
+    if let Some(file_name) = &file_name {
+        (listing.blocks, listing.text) = generate_hashmap_srcRefBlock(&data, file_name, options);
+    }
+    Ok(listing)
+}
+
+/// Checks that a file looks like a supported listing, so that a wrong file is reported before comparing
+///
+/// The file must be text with a `.file` directive & source reference comments of the configured dialect.
+pub fn validate_listing(path: &Path, options: &ParserOptions) -> Result<()> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+    let data = read_binary_file(&path.to_string_lossy())?;
+    let content = str::from_utf8(&data).map_err(|_| invalid("not a text file"))?;
+    if extract_file_name(&data).is_none() {
+        return Err(invalid("no .file directive, not an assembly listing"));
+    }
+    if !source_reference_regex(options.dialect).is_match(content) {
+        return Err(invalid(&format!(
+            "no source references, compile with -fverbose-asm or check the dialect ({})",
+            options.dialect.name()
+        )));
+    }
+    Ok(())
+}
+
 /// Extracts the the file name from the given content that is read from file
 ///
 /// Input to the function is the contents of the file & we get the file name as String wrapped in Option<> type.
@@ -261,10 +410,9 @@ pub fn generate_hashmap(file_content: &[u8], filename: &str) -> HashMap<String,S
 // eFrame parser
 
 
-pub fn generate_hashmap_srcRefBlock(file_content: &[u8], _filename: &str) -> (HashMap<String, String> , String){
+pub fn generate_hashmap_srcRefBlock(file_content: &[u8], _filename: &str, options: &ParserOptions) -> (HashMap<String, String> , String){
     let content_str = std::str::from_utf8(file_content).expect("Invalid UTF-8 sequence");
-    let regex_pattern = r#"(?m)^#\s*([\w/.\+-]+):(\d+):"#;
-    let re = Regex::new(&regex_pattern).unwrap();
+    let re = source_reference_regex(options.dialect);
     let mut map = HashMap::new();
     let mut file_string = String::new();
 
@@ -282,17 +430,17 @@ pub fn generate_hashmap_srcRefBlock(file_content: &[u8], _filename: &str) -> (Ha
                             break; // Stop if a new key pattern is found
                         }
                         
-                        let inst = iter.next().unwrap().split_whitespace().next().unwrap_or("").to_string();
+                        let line = iter.next().unwrap();
                         
                         // Stop collecting instructions if "main:" is found
-                        if inst == "main:" {
+                        if line.split_whitespace().next() == Some("main:") {
                             break;
                         }
 
-                        // Ignore lines that start with a dot, number, or underscore
-                        if !(inst.starts_with('.') || inst.starts_with(char::is_numeric) || inst.starts_with('_')) {
-                            instruction.push(inst.clone());
-                            file_string.push_str(inst.as_str())
+                        // Ignore labels & lines that start with a dot, number, or underscore unless configured
+                        if let Some(inst) = options.instruction(line) {
+                            file_string.push_str(inst.as_str());
+                            instruction.push(inst);
                         }
                     }
 
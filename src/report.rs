@@ -0,0 +1,271 @@
+use std::{collections::HashMap, fmt::Write, fs, io::Result, path::Path};
+
+use crate::{compare::*, diff::*, parser::Listing};
+
+/// Everything shown for a two-file comparison, used to export it outside the tool
+pub struct Report<'a> {
+    pub file_name: &'a str,
+    pub comparison: &'a Comparison,
+    pub listing1: &'a Listing,
+    pub listing2: &'a Listing,
+    pub source_lines: &'a HashMap<String, String>,
+}
+
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+th { background: #eee; }
+table.sortable th { cursor: pointer; }
+tr:nth-child(even) td { background: #f8f8f8; }
+code, .diff td { font-family: monospace; }
+.weak { color: #888; }
+.deleted { background: #f4c7c7 !important; }
+.inserted { background: #c7ecc7 !important; }
+";
+
+/// Sorts a table by the clicked column, numeric when every cell has a `data-sort` value
+const HTML_SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach(function (th, column) {
+  th.addEventListener('click', function () {
+    var body = th.closest('table').tBodies[0];
+    var ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    var value = function (row) {
+      var cell = row.cells[column];
+      return cell.dataset.sort !== undefined ? parseFloat(cell.dataset.sort) : cell.textContent;
+    };
+    Array.from(body.rows)
+      .sort(function (a, b) {
+        var x = value(a), y = value(b);
+        var order = typeof x === 'number' ? x - y : x.localeCompare(y, undefined, { numeric: true });
+        return ascending ? order : -order;
+      })
+      .forEach(function (row) { body.appendChild(row); });
+  });
+});
+";
+
+/// Number of most-changed keys listed in a Markdown report unless chosen otherwise
+pub const DEFAULT_TOP_KEYS: usize = 10;
+
+/// Diff lines shown per key in a Markdown report, longer diffs are cut with a note
+const MARKDOWN_DIFF_LINES: usize = 24;
+
+/// Review comments are limited to 65536 characters on GitHub, keys that would not fit are left out
+const MARKDOWN_MAX_LENGTH: usize = 60000;
+
+/// Escapes text for HTML element content & attribute values
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Whether the instructions of a key differ between the listings
+fn is_changed(result: &KeyResult) -> bool {
+    !(result.status == KeyStatus::Compared && result.distance == 0)
+}
+
+/// How much a key changed for ranking, unmatched keys count as fully changed
+fn change_rank(result: &KeyResult) -> f64 {
+    if result.is_matched() {
+        result.change_percent
+    } else {
+        100.0
+    }
+}
+
+/// Escapes the characters that would break a Markdown table cell
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('`', "'")
+}
+
+impl Report<'_> {
+    /// Instructions of a key from both listings as unified diff lines, `-` for File 1 & `+` for File 2
+    pub fn diff_lines(&self, key: &str) -> Vec<String> {
+        let instructions1: Vec<&str> = self.listing1.blocks.get(key).map(|block| block.lines().collect()).unwrap_or_default();
+        let instructions2: Vec<&str> = self.listing2.blocks.get(key).map(|block| block.lines().collect()).unwrap_or_default();
+
+        myers_diff(&instructions1, &instructions2)
+            .into_iter()
+            .map(|op| match op {
+                DiffOp::Equal { old, .. } => format!("  {}", instructions1[old]),
+                DiffOp::Delete { old } => format!("- {}", instructions1[old]),
+                DiffOp::Insert { new } => format!("+ {}", instructions2[new]),
+            })
+            .collect()
+    }
+
+    /// Markdown for a code review comment with the summary & the `top` most-changed keys with their diffs
+    ///
+    /// Keys are ranked by change %, unmatched keys first. Every diff is cut to a few lines & keys are left out once
+    /// the comment would get too long, so the result can be pasted as is.
+    pub fn to_markdown(&self, top: usize) -> String {
+        let comparison = self.comparison;
+        let mut markdown = String::new();
+
+        let _ = writeln!(markdown, "### Codegen impact: `{}`\n", self.file_name);
+        markdown.push_str("| Scope | Levenshtein Distance | Cosine Similarity | Change % |\n|---|---:|---:|---:|\n");
+        for row in comparison.summary_rows() {
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {:.2} | {} |",
+                row.scope, row.distance, comparison.cosine_similarity, row.change
+            );
+        }
+
+        let mut changed: Vec<&KeyResult> = comparison.results.iter().filter(|result| is_changed(result)).collect();
+        if changed.is_empty() {
+            markdown.push_str("\nNo key changed.\n");
+            return markdown;
+        }
+        changed.sort_by(|a, b| change_rank(b).total_cmp(&change_rank(a)).then(b.distance.cmp(&a.distance)));
+
+        let shown = changed.len().min(top);
+        let _ = writeln!(markdown, "\n#### Top {} of {} changed keys\n", shown, changed.len());
+        markdown.push_str("| Key | Distance | Change % | Source |\n|---|---:|---:|---|\n");
+        for result in &changed[..shown] {
+            let source = self.source_lines.get(&result.key).map(|line| format!("`{}`", escape_markdown_cell(line)));
+            let _ = writeln!(
+                markdown,
+                "| `{}` | {} | {} | {} |",
+                result.key,
+                result.distance_label(),
+                result.change_label(),
+                source.as_deref().unwrap_or("-")
+            );
+        }
+
+        let mut omitted = 0;
+        for result in &changed[..shown] {
+            let lines = self.diff_lines(&result.key);
+            let mut section = format!("\n<details><summary><code>{}</code> {}</summary>\n\n```diff\n", result.key, result.change_label());
+            for line in lines.iter().take(MARKDOWN_DIFF_LINES) {
+                section.push_str(line);
+                section.push('\n');
+            }
+            if lines.len() > MARKDOWN_DIFF_LINES {
+                let _ = writeln!(section, "  ... {} more line(s)", lines.len() - MARKDOWN_DIFF_LINES);
+            }
+            section.push_str("```\n</details>\n");
+
+            if markdown.len() + section.len() > MARKDOWN_MAX_LENGTH {
+                omitted += 1;
+            } else {
+                markdown.push_str(&section);
+            }
+        }
+        if omitted > 0 {
+            let _ = writeln!(markdown, "\n{} diff(s) left out to fit the comment.", omitted);
+        }
+        markdown
+    }
+
+    pub fn write_markdown(&self, path: &Path, top: usize) -> Result<()> {
+        fs::write(path, self.to_markdown(top))
+    }
+
+    /// Self-contained HTML page with the summary, the sortable per-key table & a side-by-side diff per changed key
+    ///
+    /// The styles & the sorting script are inlined, so the file can be shared without any other resource.
+    pub fn to_html(&self) -> String {
+        let comparison = self.comparison;
+        let mut html = String::new();
+        let title = format!("Dissimilarity Metrics: {}", escape_html(self.file_name));
+
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, HTML_STYLE, title
+        );
+
+        html.push_str("<h2>Summary</h2>\n<table>\n<tr><th>Scope</th><th>Levenshtein Distance</th><th>Cosine Similarity</th><th>Change %</th><th>Derivation</th></tr>\n");
+        for row in comparison.summary_rows() {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td class=\"weak\">{}</td></tr>",
+                escape_html(&row.scope),
+                escape_html(&row.distance),
+                comparison.cosine_similarity,
+                escape_html(&row.change),
+                escape_html(&row.derivation)
+            );
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Keys</h2>\n<p class=\"weak\">Click a column header to sort, click a changed key to jump to its diff.</p>\n");
+        html.push_str("<table class=\"sortable\">\n<thead><tr><th>Key</th><th>Levenshtein Distance</th><th>Cosine Similarity</th><th>Block Length</th><th>Change %</th><th>Source</th></tr></thead>\n<tbody>\n");
+        for (index, result) in comparison.results.iter().enumerate() {
+            let key = if is_changed(result) {
+                format!("<a href=\"#key-{}\">{}</a>", index, escape_html(&result.key))
+            } else {
+                escape_html(&result.key)
+            };
+            let source = self.source_lines.get(&result.key).map(|line| escape_html(line)).unwrap_or_else(|| "-".to_string());
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td><code>{}</code></td></tr>",
+                key,
+                result.distance,
+                escape_html(&result.distance_label()),
+                result.similarity,
+                escape_html(&result.similarity_label()),
+                result.block_length,
+                result.block_length,
+                result.change_percent,
+                escape_html(&result.change_label()),
+                source
+            );
+        }
+        html.push_str("</tbody>\n</table>\n");
+
+        html.push_str("<h2>Changed Keys</h2>\n");
+        for (index, result) in comparison.results.iter().enumerate().filter(|(_, result)| is_changed(result)) {
+            let _ = writeln!(html, "<h3 id=\"key-{}\">{}</h3>", index, escape_html(&result.key));
+            if let Some(source) = self.source_lines.get(&result.key) {
+                let _ = writeln!(html, "<p><code>{}</code></p>", escape_html(source));
+            }
+            html.push_str(&self.html_block_diff(&result.key));
+        }
+
+        let _ = write!(html, "<script>{}</script>\n</body>\n</html>\n", HTML_SCRIPT);
+        html
+    }
+
+    /// Instructions of a key from both listings side by side, aligned the same way as the GUI diff view
+    fn html_block_diff(&self, key: &str) -> String {
+        let block1 = self.listing1.blocks.get(key);
+        let block2 = self.listing2.blocks.get(key);
+        let instructions1: Vec<&str> = block1.map(|block| block.lines().collect()).unwrap_or_default();
+        let instructions2: Vec<&str> = block2.map(|block| block.lines().collect()).unwrap_or_default();
+
+        let mut html = String::new();
+        let _ = writeln!(
+            html,
+            "<table class=\"diff\">\n<tr><th>{}</th><th>{}</th></tr>",
+            if block1.is_some() { "File 1" } else { "File 1 (missing)" },
+            if block2.is_some() { "File 2" } else { "File 2 (missing)" }
+        );
+        for op in myers_diff(&instructions1, &instructions2) {
+            let _ = match op {
+                DiffOp::Equal { old, new } => writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    escape_html(instructions1[old]),
+                    escape_html(instructions2[new])
+                ),
+                DiffOp::Delete { old } => {
+                    writeln!(html, "<tr><td class=\"deleted\">{}</td><td></td></tr>", escape_html(instructions1[old]))
+                }
+                DiffOp::Insert { new } => {
+                    writeln!(html, "<tr><td></td><td class=\"inserted\">{}</td></tr>", escape_html(instructions2[new]))
+                }
+            };
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    pub fn write_html(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_html())
+    }
+}
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{compare::SortColumn, config::Config, matrix::MatrixMetric};
+
+/// Number of file pairs kept in the "Recent comparisons" menu
+pub const MAX_RECENT: usize = 10;
+
+/// A previously compared file pair with the sources it was shown with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentComparison {
+    pub file1: PathBuf,
+    pub file2: PathBuf,
+    pub source1: Option<PathBuf>,
+    pub source2: Option<PathBuf>,
+    pub source_root: Option<PathBuf>,
+}
+
+impl RecentComparison {
+    /// Menu entry, the file names of both listings
+    pub fn label(&self) -> String {
+        let name = |path: &PathBuf| path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        format!("{} ↔ {}", name(&self.file1), name(&self.file2))
+    }
+}
+
+/// Moves the comparison to the top of the list, dropping the oldest one past `MAX_RECENT`
+pub fn push_recent(recent: &mut Vec<RecentComparison>, comparison: RecentComparison) {
+    recent.retain(|entry| entry.file1 != comparison.file1 || entry.file2 != comparison.file2);
+    recent.insert(0, comparison);
+    recent.truncate(MAX_RECENT);
+}
+
+/// GUI state stored by eframe between sessions
+///
+/// Results are not stored, they are recomputed from the files. Window size & position and the open/closed
+/// sections are stored by eframe & egui themselves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub recent: Vec<RecentComparison>,
+    pub file1_path: Option<PathBuf>,
+    pub file2_path: Option<PathBuf>,
+    pub source1_path: Option<PathBuf>,
+    pub source2_path: Option<PathBuf>,
+    pub source_root: Option<PathBuf>,
+    pub sort_column: SortColumn,
+    pub sort_ascending: bool,
+    pub use_max_distance: bool,
+    pub max_distance: usize,
+    pub threads: usize,
+    pub matrix_metric: Option<MatrixMetric>,
+    pub compile_flags_a: String,
+    pub compile_flags_b: String,
+    pub compiler_a: String,
+    pub compiler_b: String,
+    pub git_revision_a: String,
+    pub git_revision_b: String,
+    pub git_command: String,
+    pub markdown_top: usize,
+    pub config: Config,
+    pub config_path: Option<PathBuf>,
+}
@@ -0,0 +1,203 @@
+use std::f64::consts::FRAC_PI_2;
+
+use serde::{Deserialize, Serialize};
+
+/// Metric reported as a normalized similarity, 1 for identical blocks & 0 for blocks with nothing in common
+///
+/// Unlike the raw values the similarities don't depend on the size of a block, so keys of very different sizes
+/// can be ranked & thresholded together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMetric {
+    /// `1 − distance / max(length 1, length 2)`, the same as `1 − change % / 100`
+    Levenshtein,
+    /// Angular similarity `1 − arccos(cosine) / (π / 2)`, the byte vectors are never negative so the cosine is
+    /// already between 0 & 1 but crowds close to 1
+    Cosine,
+    /// `1 − Jensen-Shannon divergence` of the opcode histograms
+    Divergence,
+    /// `1 − class distance / max(instructions 1, instructions 2)`
+    Classes,
+    /// `1 − weighted distance / max(cost of deleting block 1, cost of inserting block 2)`, at least 0 since a
+    /// cost table with expensive replacements can exceed the denominator
+    Weighted,
+    /// `LCS length / max(instructions 1, instructions 2)`
+    Diff,
+    /// `1 − unrestricted Damerau-Levenshtein distance / max(instructions 1, instructions 2)`
+    Damerau,
+}
+
+impl SimilarityMetric {
+    pub const ALL: [SimilarityMetric; 7] = [
+        SimilarityMetric::Levenshtein,
+        SimilarityMetric::Cosine,
+        SimilarityMetric::Divergence,
+        SimilarityMetric::Classes,
+        SimilarityMetric::Weighted,
+        SimilarityMetric::Diff,
+        SimilarityMetric::Damerau,
+    ];
+
+    /// Name of the metric in `config::METRICS` that the similarity belongs to
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimilarityMetric::Levenshtein => "levenshtein",
+            SimilarityMetric::Cosine => "cosine",
+            SimilarityMetric::Divergence => "divergence",
+            SimilarityMetric::Classes => "classes",
+            SimilarityMetric::Weighted => "weighted",
+            SimilarityMetric::Diff => "diff",
+            SimilarityMetric::Damerau => "damerau",
+        }
+    }
+
+    /// Column header
+    pub fn label(&self) -> &'static str {
+        match self {
+            SimilarityMetric::Levenshtein => "Levenshtein Sim",
+            SimilarityMetric::Cosine => "Cosine Sim",
+            SimilarityMetric::Divergence => "Histogram Sim",
+            SimilarityMetric::Classes => "Class Sim",
+            SimilarityMetric::Weighted => "Weighted Sim",
+            SimilarityMetric::Diff => "LCS Sim",
+            SimilarityMetric::Damerau => "Damerau Sim",
+        }
+    }
+}
+
+/// Normalized similarity of every metric for a key or a whole file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Similarities {
+    pub levenshtein: f64,
+    pub cosine: f64,
+    pub divergence: f64,
+    pub classes: f64,
+    pub weighted: f64,
+    pub diff: f64,
+    pub damerau: f64,
+}
+
+impl Default for Similarities {
+    /// Nothing compared, the sentinel of keys that exist in only one listing
+    fn default() -> Self {
+        Similarities {
+            levenshtein: -1.0,
+            cosine: -1.0,
+            divergence: -1.0,
+            classes: -1.0,
+            weighted: -1.0,
+            diff: -1.0,
+            damerau: -1.0,
+        }
+    }
+}
+
+impl Similarities {
+    /// Blocks with nothing in common, what an unmatched key contributes to the overall similarities
+    pub const UNRELATED: Similarities = Similarities {
+        levenshtein: 0.0,
+        cosine: 0.0,
+        divergence: 0.0,
+        classes: 0.0,
+        weighted: 0.0,
+        diff: 0.0,
+        damerau: 0.0,
+    };
+
+    pub fn get(&self, metric: SimilarityMetric) -> f64 {
+        match metric {
+            SimilarityMetric::Levenshtein => self.levenshtein,
+            SimilarityMetric::Cosine => self.cosine,
+            SimilarityMetric::Divergence => self.divergence,
+            SimilarityMetric::Classes => self.classes,
+            SimilarityMetric::Weighted => self.weighted,
+            SimilarityMetric::Diff => self.diff,
+            SimilarityMetric::Damerau => self.damerau,
+        }
+    }
+
+    fn get_mut(&mut self, metric: SimilarityMetric) -> &mut f64 {
+        match metric {
+            SimilarityMetric::Levenshtein => &mut self.levenshtein,
+            SimilarityMetric::Cosine => &mut self.cosine,
+            SimilarityMetric::Divergence => &mut self.divergence,
+            SimilarityMetric::Classes => &mut self.classes,
+            SimilarityMetric::Weighted => &mut self.weighted,
+            SimilarityMetric::Diff => &mut self.diff,
+            SimilarityMetric::Damerau => &mut self.damerau,
+        }
+    }
+
+    /// Average of the given similarities, each weighted by its length, 1 when there is nothing to average
+    ///
+    /// Weighting by the block length makes the Levenshtein similarity of a file `1 − change % / 100` of its
+    /// matched keys. Negative similarities were not computed & are left out of the average of their metric.
+    pub fn weighted_mean<'a>(similarities: impl Iterator<Item = (&'a Similarities, f64)>) -> Similarities {
+        let mut sums = Similarities::UNRELATED;
+        let mut totals = Similarities::UNRELATED;
+        for (similarity, weight) in similarities {
+            for metric in SimilarityMetric::ALL {
+                if similarity.get(metric) >= 0.0 {
+                    *sums.get_mut(metric) += similarity.get(metric) * weight;
+                    *totals.get_mut(metric) += weight;
+                }
+            }
+        }
+        for metric in SimilarityMetric::ALL {
+            let total = totals.get(metric);
+            let sum = sums.get_mut(metric);
+            *sum = if total > 0.0 { *sum / total } else { 1.0 };
+        }
+        sums
+    }
+}
+
+/// `1 − distance / length` kept between 0 & 1, 1 when both sides are empty
+pub fn normalized_similarity(distance: f64, length: f64) -> f64 {
+    if length > 0.0 {
+        (1.0 - distance / length).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+/// Angular similarity of the byte cosine of two blocks
+///
+/// The cosine is undefined when a block is empty, two empty blocks are identical & an empty block has nothing in
+/// common with a non-empty one.
+pub fn angular_similarity(cosine: f64, block1: &str, block2: &str) -> f64 {
+    match (block1.is_empty(), block2.is_empty()) {
+        (true, true) => 1.0,
+        (true, false) | (false, true) => 0.0,
+        (false, false) => 1.0 - cosine.clamp(0.0, 1.0).acos() / FRAC_PI_2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angular_similarity_of_empty_blocks() {
+        assert_eq!(angular_similarity(f64::NAN, "", ""), 1.0);
+        assert_eq!(angular_similarity(f64::NAN, "", "ret"), 0.0);
+        assert_eq!(angular_similarity(f64::NAN, "ret", ""), 0.0);
+    }
+
+    #[test]
+    fn angular_similarity_of_cosines() {
+        assert_eq!(angular_similarity(1.0, "ret", "ret"), 1.0);
+        assert_eq!(angular_similarity(0.0, "ret", "nop"), 0.0);
+        assert!((angular_similarity(0.5, "ret", "nop") - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn weighted_mean_leaves_out_skipped_metrics() {
+        let computed = Similarities { classes: 0.5, ..Similarities::UNRELATED };
+        let skipped = Similarities { classes: -1.0, ..Similarities::UNRELATED };
+        let mean = Similarities::weighted_mean([(&computed, 2.0), (&skipped, 6.0)].into_iter());
+        assert_eq!(mean.classes, 0.5);
+        assert_eq!(mean.levenshtein, 0.0);
+        assert_eq!(Similarities::weighted_mean(std::iter::empty()).classes, 1.0);
+    }
+}
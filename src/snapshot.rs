@@ -0,0 +1,97 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::parser::*;
+
+/// Extension of snapshot files, they are loaded in place of an assembly listing wherever one is accepted
+pub const SNAPSHOT_EXTENSION: &str = "dsnap";
+
+/// Format version written to new snapshots, older versions are rejected once the format changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Parsed listing saved to disk, so a baseline can be kept without the original `.s` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub file_name: Option<String>,
+    /// Compiler & flags that built the listing, as found in the listing or given when saving
+    pub compiler: Option<String>,
+    pub flags: Option<String>,
+    /// Listing the snapshot was taken from
+    pub listing: Option<String>,
+    /// Key → mnemonics joined by `\n`, sorted so the files diff cleanly
+    pub blocks: BTreeMap<String, String>,
+    /// Mnemonic stream used for the whole-file cosine similarity
+    pub text: String,
+}
+
+impl Snapshot {
+    pub fn to_listing(&self) -> Listing {
+        Listing {
+            file_name: self.file_name.clone(),
+            blocks: self.blocks.clone().into_iter().collect(),
+            text: self.text.clone(),
+        }
+    }
+}
+
+/// Whether the path names a snapshot file rather than an assembly listing
+pub fn is_snapshot(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == SNAPSHOT_EXTENSION)
+}
+
+/// Compiler version from the `.ident` directive & flags from the `-fverbose-asm` header of a gcc listing
+fn listing_metadata(content: &str) -> (Option<String>, Option<String>) {
+    let ident = Regex::new(r#"\.ident\s+"([^"]+)""#).unwrap();
+    let options = Regex::new(r"(?m)^#\s*options passed:\s*(.*)$").unwrap();
+    let compiler = ident.captures(content).map(|cap| cap[1].to_string());
+    let flags = options.captures(content).map(|cap| cap[1].trim().to_string());
+    (compiler, flags)
+}
+
+/// Parses a listing into a snapshot, `compiler` & `flags` override what is found in the listing
+pub fn create_snapshot(
+    listing_path: &Path,
+    compiler: Option<String>,
+    flags: Option<String>,
+    options: &ParserOptions,
+) -> Result<Snapshot> {
+    let data = read_binary_file(&listing_path.to_string_lossy())?;
+    let listing = load_listing(listing_path, options)?;
+    let (found_compiler, found_flags) = listing_metadata(&String::from_utf8_lossy(&data));
+
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        file_name: listing.file_name,
+        compiler: compiler.or(found_compiler),
+        flags: flags.or(found_flags),
+        listing: Some(listing_path.display().to_string()),
+        blocks: listing.blocks.into_iter().collect(),
+        text: listing.text,
+    })
+}
+
+pub fn write_snapshot(snapshot: &Snapshot, path: &Path) -> Result<()> {
+    let content = serde_json::to_string(snapshot).map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+    fs::write(path, content)
+}
+
+pub fn read_snapshot(path: &Path) -> Result<Snapshot> {
+    let content = fs::read_to_string(path)?;
+    let snapshot: Snapshot = serde_json::from_str(&content)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), error)))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{}: unsupported snapshot version {}, expected {}", path.display(), snapshot.version, SNAPSHOT_VERSION),
+        ));
+    }
+    Ok(snapshot)
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fs, io::Result, path::Path};
+
+use crate::{compare::key_order, diff::*, parser::Listing};
+
+/// Strips the leading `./` so that `./file.c` & `file.c` name the same source
+pub fn normalize_source_path(path: &str) -> &str {
+    path.trim_start_matches("./")
+}
+
+/// Whether the file of a key is the listing's own source file
+///
+/// The `.file` directive only holds the name passed to the compiler, often without directories, while the source
+/// references can carry the full path.
+pub fn is_listing_source(key_file: &str, file_name: &str) -> bool {
+    let key_file = normalize_source_path(key_file);
+    let file_name = normalize_source_path(file_name);
+    key_file == file_name || key_file.ends_with(&format!("/{}", file_name))
+}
+
+/// Maps the line numbers of the new version of a source file to the old version
+///
+/// Unchanged lines are matched with a text diff. Inside a changed region, deleted & inserted lines are paired in
+/// order so that a line edited in place still corresponds to its previous version. Line numbers are 1 based &
+/// lines that only exist in the new version are missing from the map.
+pub fn map_source_lines(old_source: &str, new_source: &str) -> HashMap<usize, usize> {
+    let old_lines: Vec<&str> = old_source.lines().collect();
+    let new_lines: Vec<&str> = new_source.lines().collect();
+    let mut mapping = HashMap::new();
+
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+    for op in myers_diff(&old_lines, &new_lines) {
+        match op {
+            DiffOp::Delete { old } => deleted.push(old),
+            DiffOp::Insert { new } => inserted.push(new),
+            DiffOp::Equal { old, new } => {
+                for (old, new) in deleted.drain(..).zip(inserted.drain(..)) {
+                    mapping.insert(new + 1, old + 1);
+                }
+                mapping.insert(new + 1, old + 1);
+            }
+        }
+    }
+    for (old, new) in deleted.into_iter().zip(inserted) {
+        mapping.insert(new + 1, old + 1);
+    }
+    mapping
+}
+
+/// Re-keys the blocks of a listing built from the new source through a line mapping
+///
+/// Only keys of the listing's own source file are re-keyed, keys of included headers are kept as is. Lines without
+/// a counterpart in the old source become `file:+line` so they never collide with an old line number.
+pub fn rekey_listing(listing: &Listing, mapping: &HashMap<usize, usize>) -> Listing {
+    let Some(file_name) = listing.file_name.as_deref() else {
+        return listing.clone();
+    };
+
+    // Walk the blocks in source order, so blocks that map to the same old line are joined in that order
+    let mut entries: Vec<(&String, &String)> = listing.blocks.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| key_order(a).cmp(&key_order(b)).then(a.cmp(b)));
+
+    let mut blocks: HashMap<String, String> = HashMap::new();
+    for (key, value) in entries {
+        let new_key = match key.rsplit_once(':') {
+            Some((file, line)) if is_listing_source(file, file_name) => {
+                match line.parse::<usize>().ok().and_then(|line| mapping.get(&line)) {
+                    Some(old_line) => format!("{}:{}", file, old_line),
+                    None => format!("{}:+{}", file, line),
+                }
+            }
+            _ => key.clone(),
+        };
+
+        // Append to existing value if the key already exists
+        blocks.entry(new_key).and_modify(|e: &mut String| {
+            if !e.is_empty() {
+                e.push('\n');
+            }
+            e.push_str(value);
+        }).or_insert_with(|| value.clone());
+    }
+
+    Listing {
+        file_name: listing.file_name.clone(),
+        blocks,
+        text: listing.text.clone(),
+    }
+}
+
+/// Reads both versions of the source file & re-keys the listing of the new version onto the old line numbers
+pub fn remap_listing(listing: &Listing, old_source_path: &Path, new_source_path: &Path) -> Result<Listing> {
+    let old_source = fs::read_to_string(old_source_path)?;
+    let new_source = fs::read_to_string(new_source_path)?;
+    let mapping = map_source_lines(&old_source, &new_source);
+    Ok(rekey_listing(listing, &mapping))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserted_lines_shift_the_lines_below() {
+        let mapping = map_source_lines("a\nb\nc\n", "a\nnew\nb\nc\n");
+        assert_eq!(mapping, HashMap::from([(1, 1), (3, 2), (4, 3)]));
+    }
+
+    #[test]
+    fn deleted_lines_are_skipped() {
+        let mapping = map_source_lines("a\nb\nc\nd\n", "a\nd\n");
+        assert_eq!(mapping, HashMap::from([(1, 1), (2, 4)]));
+    }
+
+    #[test]
+    fn edited_lines_keep_their_counterpart() {
+        let mapping = map_source_lines("a\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(mapping, HashMap::from([(1, 1), (2, 2), (3, 3)]));
+    }
+
+    #[test]
+    fn rekey_maps_own_source_lines_only() {
+        let blocks = [("src/a.c:1", "movl"), ("src/a.c:2", "addl"), ("src/a.c:4", "ret"), ("a.h:2", "nop")];
+        let listing = Listing {
+            file_name: Some("a.c".to_string()),
+            blocks: blocks.iter().map(|(key, block)| (key.to_string(), block.to_string())).collect(),
+            text: String::new(),
+        };
+        // Line 2 was inserted, line 4 used to be line 3
+        let mapping = HashMap::from([(1, 1), (3, 2), (4, 3)]);
+        let rekeyed = rekey_listing(&listing, &mapping);
+
+        let mut keys: Vec<&str> = rekeyed.blocks.keys().map(|key| key.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, ["a.h:2", "src/a.c:+2", "src/a.c:1", "src/a.c:3"]);
+        assert_eq!(rekeyed.blocks["src/a.c:3"], "ret");
+    }
+
+    #[test]
+    fn rekey_merges_blocks_that_map_to_the_same_line() {
+        let blocks = [("a.c:9", "movl"), ("a.c:10", "addl"), ("a.c:11", "ret")];
+        let listing = Listing {
+            file_name: Some("a.c".to_string()),
+            blocks: blocks.iter().map(|(key, block)| (key.to_string(), block.to_string())).collect(),
+            text: String::new(),
+        };
+        let rekeyed = rekey_listing(&listing, &HashMap::from([(9, 1), (10, 1), (11, 1)]));
+        assert_eq!(rekeyed.blocks.len(), 1);
+        assert_eq!(rekeyed.blocks["a.c:1"], "movl\naddl\nret");
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::source_map::normalize_source_path;
+
+/// Finds the source file referenced by a key in the given search roots
+///
+/// Absolute paths are used as they are, relative paths are tried against every root in order.
+pub fn resolve_source_path(file: &str, roots: &[PathBuf]) -> Option<PathBuf> {
+    let path = Path::new(file);
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    roots
+        .iter()
+        .map(|root| root.join(normalize_source_path(file)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Looks up the source text of every `file:line` key
+///
+/// Each source file is read once. Keys whose file cannot be found, or whose line is out of range, are missing from
+/// the returned map.
+pub fn load_source_lines<'a>(keys: impl Iterator<Item = &'a str>, roots: &[PathBuf]) -> HashMap<String, String> {
+    let mut files: HashMap<String, Option<Vec<String>>> = HashMap::new();
+    let mut source_lines = HashMap::new();
+
+    for key in keys {
+        let Some((file, line)) = key.rsplit_once(':') else {
+            continue;
+        };
+        // Lines that only exist in the new source (`file:+line`) have no text in the old one
+        if line.starts_with('+') {
+            continue;
+        }
+        let Ok(line) = line.parse::<usize>() else {
+            continue;
+        };
+
+        let lines = files.entry(file.to_string()).or_insert_with(|| {
+            resolve_source_path(file, roots)
+                .and_then(|path| fs::read_to_string(path).ok())
+                .map(|content| content.lines().map(|line| line.to_string()).collect())
+        });
+        if let Some(text) = lines.as_ref().and_then(|lines| lines.get(line.wrapping_sub(1))) {
+            source_lines.insert(key.to_string(), text.trim().to_string());
+        }
+    }
+    source_lines
+}
+
+/// Search roots for the sources of a listing: the configured source root first, then the listing's directory
+pub fn source_roots(source_root: Option<&Path>, listing_path: &Path) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = source_root.map(|root| root.to_path_buf()).into_iter().collect();
+    roots.push(listing_path.parent().map(|dir| dir.to_path_buf()).unwrap_or_default());
+    roots
+}
@@ -0,0 +1,111 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::compare::*;
+
+/// How often the watched files are checked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Modification time & size of a file, None when it can't be read, e.g. while the compiler replaces it
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &PathBuf) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Polls files for changes on disk
+///
+/// A change is only reported once the files are stable for one poll, so a listing that is still being written is
+/// not compared.
+#[derive(Debug, Clone, Default)]
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    seen: Vec<Stamp>,
+    pending: Option<Vec<Stamp>>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let seen = paths.iter().map(stamp).collect();
+        FileWatcher { paths, seen, pending: None }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Whether any file changed since the last reported change
+    pub fn poll(&mut self) -> bool {
+        let current: Vec<Stamp> = self.paths.iter().map(stamp).collect();
+        if current == self.seen {
+            self.pending = None;
+            return false;
+        }
+        if self.pending.as_ref() == Some(&current) && current.iter().all(|stamp| stamp.is_some()) {
+            self.seen = current;
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some(current);
+        false
+    }
+}
+
+/// Keys whose metrics differ between two runs of the same comparison, including keys new in the current run
+///
+/// Floats are compared with `total_cmp` so the NaN similarity of an empty block is equal to itself.
+pub fn changed_keys(previous: &Comparison, current: &Comparison) -> HashSet<String> {
+    let previous: HashMap<&str, &KeyResult> = previous.results.iter().map(|result| (result.key.as_str(), result)).collect();
+    current
+        .results
+        .iter()
+        .filter(|result| match previous.get(result.key.as_str()) {
+            Some(before) => {
+                before.status != result.status
+                    || before.distance != result.distance
+                    || before.block_length != result.block_length
+                    || before.similarity.total_cmp(&result.similarity).is_ne()
+                    || before.divergence.total_cmp(&result.divergence).is_ne()
+                    || before.class_distance != result.class_distance
+                    || before.weighted_distance.total_cmp(&result.weighted_distance).is_ne()
+                    || before.hunks != result.hunks
+                    || before.changed_lines != result.changed_lines
+                    || before.damerau_distance != result.damerau_distance
+                    || before.osa_distance != result.osa_distance
+            }
+            None => true,
+        })
+        .map(|result| result.key.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparison(blocks: &[(&str, &str, &str)]) -> Comparison {
+        let options = CompareOptions::default();
+        let results = blocks.iter().map(|(key, block1, block2)| compare_block(key, block1, block2, &options)).collect();
+        Comparison { results, ..Default::default() }
+    }
+
+    #[test]
+    fn unchanged_results_are_not_reported() {
+        // The cosine similarity of empty blocks is NaN
+        let blocks = [("a.c:1", "", ""), ("a.c:2", "movl\nret", "movq\nret")];
+        assert!(changed_keys(&comparison(&blocks), &comparison(&blocks)).is_empty());
+    }
+
+    #[test]
+    fn changed_and_new_keys_are_reported() {
+        let previous = comparison(&[("a.c:1", "movl", "movl"), ("a.c:2", "ret", "ret")]);
+        let current = comparison(&[("a.c:1", "movl", "addl"), ("a.c:2", "ret", "ret"), ("a.c:3", "nop", "")]);
+        let changed = changed_keys(&previous, &current);
+        assert_eq!(changed, HashSet::from(["a.c:1".to_string(), "a.c:3".to_string()]));
+    }
+}